@@ -0,0 +1,215 @@
+// phf_mut – Perfectly hashed mutable containers
+// Copyright (C) 2017  Ben Wiederhake
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+
+use {PerfectHash, HashEquivalent, HashInverse, StaticPerfectHash};
+
+const BASES: [u8; 4] = *b"ACGT";
+
+/// The 2-bit code of a nucleotide, accepting both upper and lower case.
+/// Uracil is treated like thymine, so RNA can be used, too.
+fn base_code(base: u8) -> usize {
+    match base {
+        b'A' | b'a' => 0,
+        b'C' | b'c' => 1,
+        b'G' | b'g' => 2,
+        b'T' | b't' | b'U' | b'u' => 3,
+        _ => panic!("Invalid nucleotide {:?}", base as char),
+    }
+}
+
+/// DNA sequences of length `k`, given as ASCII strings over `ACGT`.
+/// The hash of a k-mer is its packed 2-bit encoding (with `A`, `C`, `G`, `T`
+/// being 0 to 3, and the first base being the most significant one),
+/// so the hash domain is `0..4^k`.
+/// Inversion always yields upper-case sequences.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Kmer {
+    k: usize,
+}
+
+impl Kmer {
+    /// Create a new hasher for k-mers of length `k`.
+    pub fn new(k: usize) -> Self {
+        assert!(2 * k < 8 * std::mem::size_of::<usize>());
+        Kmer { k }
+    }
+
+    /// The length of each k-mer.
+    pub fn k(&self) -> usize {
+        self.k
+    }
+
+    /// The packed 2-bit encoding of a sequence of length `k`,
+    /// which is the same as its hash.  Unlike `hash`, this only borrows
+    /// the sequence, which is handy for sliding windows over a genome.
+    pub fn encode(&self, bases: &[u8]) -> usize {
+        assert_eq!(self.k, bases.len());
+        bases.iter().fold(0, |acc, &b| (acc << 2) | base_code(b))
+    }
+
+    /// Decode a packed 2-bit encoding into a sequence of length `k`.
+    pub fn decode(&self, packed: usize) -> Vec<u8> {
+        assert!(packed < self.size());
+        (0..self.k).rev().map(|i| BASES[(packed >> (2 * i)) & 3]).collect()
+    }
+}
+
+impl PerfectHash for Kmer {
+    type K = Vec<u8>;
+
+    fn hash(&self, k: Self::K) -> usize {
+        self.encode(&k)
+    }
+
+    fn hash_ref(&self, k: &Self::K) -> usize {
+        self.encode(k)
+    }
+
+    fn size(&self) -> usize {
+        1 << (2 * self.k)
+    }
+}
+
+impl HashEquivalent<[u8]> for Kmer {
+    fn hash_equivalent(&self, bases: &[u8]) -> usize {
+        self.encode(bases)
+    }
+}
+
+impl HashEquivalent<str> for Kmer {
+    fn hash_equivalent(&self, bases: &str) -> usize {
+        self.encode(bases.as_bytes())
+    }
+}
+
+impl HashInverse for Kmer {
+    fn invert(&self, hash: usize) -> Self::K {
+        self.decode(hash)
+    }
+}
+
+/// The 64 codons, given as three ASCII nucleotides (DNA or RNA).
+/// Codons are laid out like k-mers of length 3, i.e., `AAA` has hash 0
+/// and `TTT` has hash 63.  Inversion always yields upper-case DNA.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Codons;
+
+impl PerfectHash for Codons {
+    type K = [u8; 3];
+
+    fn hash(&self, k: Self::K) -> usize {
+        (base_code(k[0]) << 4) | (base_code(k[1]) << 2) | base_code(k[2])
+    }
+
+    fn size(&self) -> usize {
+        64
+    }
+}
+
+impl HashInverse for Codons {
+    fn invert(&self, hash: usize) -> Self::K {
+        assert!(hash < 64);
+        [BASES[hash >> 4], BASES[(hash >> 2) & 3], BASES[hash & 3]]
+    }
+}
+
+impl StaticPerfectHash for Codons {
+    const SIZE: usize = 64;
+}
+
+/// One-letter codes of the 20 standard amino acids, in alphabetical order.
+const AMINO_ACIDS: [u8; 20] = *b"ACDEFGHIKLMNPQRSTVWY";
+
+/// One-letter codes of ambiguous amino acids:
+/// Asx, Glx, Xle and "any".
+const AMBIGUOUS_AMINO_ACIDS: [u8; 4] = *b"BZJX";
+
+/// Amino acids, given as ASCII one-letter codes.
+/// By default, only the 20 standard amino acids are part of the domain,
+/// in alphabetical order of their codes.  Optionally, the stop symbol `*`
+/// and the ambiguity codes `B`, `Z`, `J` and `X` are appended, in this order.
+/// Lower-case codes are accepted, but inversion always yields upper case.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct AminoAcids {
+    stop: bool,
+    ambiguous: bool,
+}
+
+impl AminoAcids {
+    /// Create a new hasher for the 20 standard amino acids.
+    pub fn new() -> Self {
+        AminoAcids::default()
+    }
+
+    /// Also include the stop symbol `*`.
+    pub fn with_stop(self) -> Self {
+        AminoAcids { stop: true, ..self }
+    }
+
+    /// Also include the ambiguity codes `B`, `Z`, `J` and `X`.
+    pub fn with_ambiguous(self) -> Self {
+        AminoAcids { ambiguous: true, ..self }
+    }
+
+    /// Whether the stop symbol `*` is part of the domain.
+    pub fn has_stop(&self) -> bool {
+        self.stop
+    }
+
+    /// Whether the ambiguity codes are part of the domain.
+    pub fn has_ambiguous(&self) -> bool {
+        self.ambiguous
+    }
+}
+
+impl PerfectHash for AminoAcids {
+    type K = u8;
+
+    fn hash(&self, k: Self::K) -> usize {
+        let code = k.to_ascii_uppercase();
+        let stop = self.stop as usize;
+        if let Some(i) = AMINO_ACIDS.iter().position(|&c| c == code) {
+            i
+        } else if self.stop && code == b'*' {
+            20
+        } else if let Some(i) = AMBIGUOUS_AMINO_ACIDS.iter()
+            .position(|&c| c == code)
+            .filter(|_| self.ambiguous) {
+            20 + stop + i
+        } else {
+            panic!("Invalid amino acid {:?}", k as char)
+        }
+    }
+
+    fn size(&self) -> usize {
+        20 + self.stop as usize + 4 * self.ambiguous as usize
+    }
+}
+
+impl HashInverse for AminoAcids {
+    fn invert(&self, hash: usize) -> Self::K {
+        assert!(hash < self.size());
+        let stop = self.stop as usize;
+        if hash < 20 {
+            AMINO_ACIDS[hash]
+        } else if hash < 20 + stop {
+            b'*'
+        } else {
+            AMBIGUOUS_AMINO_ACIDS[hash - 20 - stop]
+        }
+    }
+}
@@ -0,0 +1,127 @@
+// phf_mut – Perfectly hashed mutable containers
+// Copyright (C) 2017  Ben Wiederhake
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+
+use chrono::{Datelike, Month, NaiveDate, Weekday};
+use {PerfectHash, HashInverse, StaticPerfectHash};
+
+const WEEKDAYS: [Weekday; 7] = [
+    Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu,
+    Weekday::Fri, Weekday::Sat, Weekday::Sun,
+];
+
+const MONTHS: [Month; 12] = [
+    Month::January, Month::February, Month::March, Month::April,
+    Month::May, Month::June, Month::July, Month::August,
+    Month::September, Month::October, Month::November, Month::December,
+];
+
+/// The 7 days of the week, starting with Monday.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Weekdays;
+
+impl PerfectHash for Weekdays {
+    type K = Weekday;
+
+    fn hash(&self, k: Self::K) -> usize {
+        k.num_days_from_monday() as usize
+    }
+
+    fn size(&self) -> usize {
+        7
+    }
+}
+
+impl HashInverse for Weekdays {
+    fn invert(&self, hash: usize) -> Self::K {
+        WEEKDAYS[hash]
+    }
+}
+
+impl StaticPerfectHash for Weekdays {
+    const SIZE: usize = 7;
+}
+
+/// The 12 months of the year, starting with January.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Months;
+
+impl PerfectHash for Months {
+    type K = Month;
+
+    fn hash(&self, k: Self::K) -> usize {
+        k.number_from_month() as usize - 1
+    }
+
+    fn size(&self) -> usize {
+        12
+    }
+}
+
+impl HashInverse for Months {
+    fn invert(&self, hash: usize) -> Self::K {
+        MONTHS[hash]
+    }
+}
+
+impl StaticPerfectHash for Months {
+    const SIZE: usize = 12;
+}
+
+/// All days of a specific year, keyed by date.
+/// The domain has 365 or 366 days, depending on whether it's a leap year.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct DaysOfYear {
+    year: i32,
+}
+
+impl DaysOfYear {
+    /// Create a new hasher for the days of the given year.
+    pub fn new(year: i32) -> Self {
+        assert!(NaiveDate::from_yo_opt(year, 1).is_some(),
+                "Year {} out of range", year);
+        DaysOfYear { year }
+    }
+
+    /// The year of all days in the domain.
+    pub fn year(&self) -> i32 {
+        self.year
+    }
+}
+
+impl PerfectHash for DaysOfYear {
+    type K = NaiveDate;
+
+    fn hash(&self, k: Self::K) -> usize {
+        assert_eq!(self.year, k.year(), "Date {} is in the wrong year", k);
+        k.ordinal0() as usize
+    }
+
+    fn size(&self) -> usize {
+        if NaiveDate::from_yo_opt(self.year, 366).is_some() {
+            366
+        } else {
+            365
+        }
+    }
+}
+
+impl HashInverse for DaysOfYear {
+    fn invert(&self, hash: usize) -> Self::K {
+        NaiveDate::from_yo_opt(self.year, hash as u32 + 1)
+            .expect("Day of year out of range")
+    }
+}
@@ -0,0 +1,143 @@
+// phf_mut – Perfectly hashed mutable containers
+// Copyright (C) 2017  Ben Wiederhake
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+
+use {PerfectHash, HashInverse, OrderedHash, StaticPerfectHash};
+
+/// The suit of a playing card.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Suit {
+    Clubs,
+    Diamonds,
+    Hearts,
+    Spades,
+}
+
+const SUITS: [Suit; 4] = [Suit::Clubs, Suit::Diamonds, Suit::Hearts, Suit::Spades];
+
+/// The rank of a playing card, with aces being high.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Rank {
+    Two,
+    Three,
+    Four,
+    Five,
+    Six,
+    Seven,
+    Eight,
+    Nine,
+    Ten,
+    Jack,
+    Queen,
+    King,
+    Ace,
+}
+
+const RANKS: [Rank; 13] = [
+    Rank::Two, Rank::Three, Rank::Four, Rank::Five, Rank::Six,
+    Rank::Seven, Rank::Eight, Rank::Nine, Rank::Ten,
+    Rank::Jack, Rank::Queen, Rank::King, Rank::Ace,
+];
+
+/// The 52 cards of a standard deck, keyed by `(Suit, Rank)`.
+/// All cards of a suit are stored together, ordered by rank.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Cards;
+
+impl PerfectHash for Cards {
+    type K = (Suit, Rank);
+
+    fn hash(&self, (suit, rank): Self::K) -> usize {
+        suit as usize * 13 + rank as usize
+    }
+
+    fn size(&self) -> usize {
+        52
+    }
+}
+
+impl HashInverse for Cards {
+    fn invert(&self, hash: usize) -> Self::K {
+        assert!(hash < 52);
+        (SUITS[hash / 13], RANKS[hash % 13])
+    }
+}
+
+impl StaticPerfectHash for Cards {
+    const SIZE: usize = 52;
+}
+
+impl OrderedHash for Cards {}
+
+/// A playing card from a deck that may contain jokers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Card {
+    /// One of the 52 regular cards.
+    Regular(Suit, Rank),
+    /// The joker with the given number, counting from 0.
+    Joker(usize),
+}
+
+/// The 52 cards of a standard deck plus some jokers.
+/// The regular cards are laid out as in `Cards`, followed by the jokers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct CardsWithJokers {
+    jokers: usize,
+}
+
+impl CardsWithJokers {
+    /// Create a new hasher for a deck with the given number of jokers.
+    pub fn new(jokers: usize) -> Self {
+        CardsWithJokers { jokers }
+    }
+
+    /// The number of jokers in the deck.
+    pub fn jokers(&self) -> usize {
+        self.jokers
+    }
+}
+
+impl PerfectHash for CardsWithJokers {
+    type K = Card;
+
+    fn hash(&self, k: Self::K) -> usize {
+        match k {
+            Card::Regular(suit, rank) => Cards.hash((suit, rank)),
+            Card::Joker(i) => {
+                assert!(i < self.jokers, "No joker number {}", i);
+                52 + i
+            }
+        }
+    }
+
+    fn size(&self) -> usize {
+        52 + self.jokers
+    }
+}
+
+impl HashInverse for CardsWithJokers {
+    fn invert(&self, hash: usize) -> Self::K {
+        assert!(hash < self.size());
+        if hash < 52 {
+            let (suit, rank) = Cards.invert(hash);
+            Card::Regular(suit, rank)
+        } else {
+            Card::Joker(hash - 52)
+        }
+    }
+}
+
+impl OrderedHash for CardsWithJokers {}
@@ -0,0 +1,163 @@
+// phf_mut – Perfectly hashed mutable containers
+// Copyright (C) 2017  Ben Wiederhake
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+
+use {PerfectHash, HashInverse, OrderedHash, StaticPerfectHash, Set};
+
+/// A square of a chess board.  Files and ranks are counted from 0,
+/// so `a1` is `Square::new(0, 0)` and `h8` is `Square::new(7, 7)`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Square {
+    file: u8,
+    rank: u8,
+}
+
+impl Square {
+    /// Create a new square from its file (`a` to `h`, as 0 to 7)
+    /// and its rank (`1` to `8`, as 0 to 7).
+    pub fn new(file: u8, rank: u8) -> Self {
+        assert!(file < 8 && rank < 8);
+        Square { file, rank }
+    }
+
+    /// The file, from 0 (`a`) to 7 (`h`).
+    pub fn file(&self) -> u8 {
+        self.file
+    }
+
+    /// The rank, from 0 (`1`) to 7 (`8`).
+    pub fn rank(&self) -> u8 {
+        self.rank
+    }
+}
+
+/// The 64 squares of a chess board, in little-endian rank-file order,
+/// i.e., `a1` has hash 0, `b1` has hash 1, and `h8` has hash 63.
+/// This matches the usual bitboard layout, also see `Set::bitboard`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Squares;
+
+impl PerfectHash for Squares {
+    type K = Square;
+
+    fn hash(&self, k: Self::K) -> usize {
+        k.rank as usize * 8 + k.file as usize
+    }
+
+    fn size(&self) -> usize {
+        64
+    }
+}
+
+impl HashInverse for Squares {
+    fn invert(&self, hash: usize) -> Self::K {
+        assert!(hash < 64);
+        Square::new((hash % 8) as u8, (hash / 8) as u8)
+    }
+}
+
+impl StaticPerfectHash for Squares {
+    const SIZE: usize = 64;
+}
+
+/// The color of a chess piece.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Color {
+    White,
+    Black,
+}
+
+/// The kind of a chess piece, regardless of its color.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum PieceKind {
+    Pawn,
+    Knight,
+    Bishop,
+    Rook,
+    Queen,
+    King,
+}
+
+const PIECE_KINDS: [PieceKind; 6] = [
+    PieceKind::Pawn, PieceKind::Knight, PieceKind::Bishop,
+    PieceKind::Rook, PieceKind::Queen, PieceKind::King,
+];
+
+/// A chess piece, i.e., a kind of piece together with its color.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Piece {
+    pub color: Color,
+    pub kind: PieceKind,
+}
+
+impl Piece {
+    /// Create a new piece of the given color and kind.
+    pub fn new(color: Color, kind: PieceKind) -> Self {
+        Piece { color, kind }
+    }
+}
+
+/// The 12 different chess pieces.  All white pieces come first,
+/// each color in the order pawn, knight, bishop, rook, queen, king.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Pieces;
+
+impl PerfectHash for Pieces {
+    type K = Piece;
+
+    fn hash(&self, k: Self::K) -> usize {
+        k.color as usize * 6 + k.kind as usize
+    }
+
+    fn size(&self) -> usize {
+        12
+    }
+}
+
+impl HashInverse for Pieces {
+    fn invert(&self, hash: usize) -> Self::K {
+        assert!(hash < 12);
+        let color = if hash < 6 { Color::White } else { Color::Black };
+        Piece::new(color, PIECE_KINDS[hash % 6])
+    }
+}
+
+impl StaticPerfectHash for Pieces {
+    const SIZE: usize = 12;
+}
+
+impl OrderedHash for Pieces {}
+
+/// All combinations of piece and square, e.g. for piece-square tables
+/// or Zobrist keys.  All squares of a single piece are stored together.
+pub type PieceSquares = (Pieces, Squares);
+
+impl Set<Squares> {
+    /// Create a new set of squares from a bitboard,
+    /// where bit `i` corresponds to the square with hash `i`.
+    pub fn from_bitboard(bits: u64) -> Self {
+        let mut set = Set::new(Squares);
+        set.backing = Box::new([bits as u32, (bits >> 32) as u32]);
+        set.len = bits.count_ones() as usize;
+        set
+    }
+
+    /// The bitboard of this set, where bit `i` corresponds to the square
+    /// with hash `i`.  Also see `from_bitboard`.
+    pub fn bitboard(&self) -> u64 {
+        u64::from(self.backing[0]) | u64::from(self.backing[1]) << 32
+    }
+}
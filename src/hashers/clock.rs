@@ -0,0 +1,70 @@
+// phf_mut – Perfectly hashed mutable containers
+// Copyright (C) 2017  Ben Wiederhake
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+
+use {PerfectHash, HashInverse};
+
+const MINUTES_PER_DAY: u32 = 24 * 60;
+
+/// Times within a day, keyed by `(hour, minute)`, grouped into buckets
+/// of a fixed number of minutes.  All times within a bucket share a slot,
+/// and inversion yields the start of the bucket.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TimeOfDay {
+    resolution: u32,
+}
+
+impl TimeOfDay {
+    /// Create a new hasher with one slot per minute, i.e., 1440 slots.
+    pub fn minutes() -> Self {
+        TimeOfDay::with_resolution(1)
+    }
+
+    /// Create a new hasher with one slot per `resolution` minutes,
+    /// e.g. 15 for quarter-hours or 60 for hours.
+    /// The resolution must evenly divide a day.
+    pub fn with_resolution(resolution: u32) -> Self {
+        assert!(resolution > 0 && MINUTES_PER_DAY.is_multiple_of(resolution),
+                "Resolution {} does not divide a day", resolution);
+        TimeOfDay { resolution }
+    }
+
+    /// The number of minutes per slot.
+    pub fn resolution(&self) -> u32 {
+        self.resolution
+    }
+}
+
+impl PerfectHash for TimeOfDay {
+    type K = (u32, u32);
+
+    fn hash(&self, (hour, minute): Self::K) -> usize {
+        assert!(hour < 24 && minute < 60, "Invalid time {}:{}", hour, minute);
+        ((hour * 60 + minute) / self.resolution) as usize
+    }
+
+    fn size(&self) -> usize {
+        (MINUTES_PER_DAY / self.resolution) as usize
+    }
+}
+
+impl HashInverse for TimeOfDay {
+    fn invert(&self, hash: usize) -> Self::K {
+        assert!(hash < self.size());
+        let start = hash as u32 * self.resolution;
+        (start / 60, start % 60)
+    }
+}
@@ -0,0 +1,299 @@
+// phf_mut – Perfectly hashed mutable containers
+// Copyright (C) 2017  Ben Wiederhake
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+
+use std::convert::TryFrom;
+use std::marker::PhantomData;
+use {PerfectHash, HashEquivalent, HashInverse, OrderedHash};
+
+/// The binomial coefficient "`n` choose `k`".
+fn binomial(n: usize, k: usize) -> usize {
+    if k > n {
+        return 0;
+    }
+    let k = std::cmp::min(k, n - k);
+    let mut result: usize = 1;
+    for i in 0..k {
+        /* Always divisible, as 'result * (n - i)' is 'i + 1' times
+         * a binomial coefficient.  The product may exceed usize even if
+         * the quotient does not, hence the detour through u128. */
+        let next = result as u128 * (n - i) as u128 / (i + 1) as u128;
+        result = usize::try_from(next).expect("binomial coefficient overflows usize");
+    }
+    result
+}
+
+/// All `k`-element subsets of the numbers below `n`, ranked by the
+/// combinatorial number system.  Keys are vectors of distinct numbers,
+/// in any order.  Inversion always yields the elements in ascending order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Combinations {
+    n: usize,
+    k: usize,
+}
+
+impl Combinations {
+    /// Create a new hasher for all `k`-element subsets of `0..n`.
+    pub fn new(n: usize, k: usize) -> Self {
+        assert!(k <= n);
+        Combinations { n, k }
+    }
+
+    /// The size of the base set.
+    pub fn n(&self) -> usize {
+        self.n
+    }
+
+    /// The number of elements in each subset.
+    pub fn k(&self) -> usize {
+        self.k
+    }
+}
+
+impl PerfectHash for Combinations {
+    type K = Vec<usize>;
+
+    fn hash(&self, k: Self::K) -> usize {
+        self.hash_equivalent(&k[..])
+    }
+
+    fn hash_ref(&self, k: &Self::K) -> usize {
+        self.hash_equivalent(&k[..])
+    }
+
+    fn size(&self) -> usize {
+        binomial(self.n, self.k)
+    }
+}
+
+impl HashEquivalent<[usize]> for Combinations {
+    fn hash_equivalent(&self, k: &[usize]) -> usize {
+        assert_eq!(self.k, k.len());
+        let mut hash = 0;
+        for &c in k {
+            assert!(c < self.n);
+            /* Position of 'c' if the elements were sorted. */
+            let i = k.iter().filter(|&&other| other < c).count();
+            assert_eq!(1, k.iter().filter(|&&other| other == c).count(),
+                       "Duplicate element {}", c);
+            hash += binomial(c, i + 1);
+        }
+        hash
+    }
+}
+
+impl HashInverse for Combinations {
+    fn invert(&self, hash: usize) -> Self::K {
+        assert!(hash < self.size());
+        let mut rest = hash;
+        let mut upper = self.n;
+        let mut subset = vec![0; self.k];
+        for i in (0..self.k).rev() {
+            /* Find the largest 'c' with 'binomial(c, i + 1) <= rest'. */
+            let mut c = upper - 1;
+            while binomial(c, i + 1) > rest {
+                c -= 1;
+            }
+            rest -= binomial(c, i + 1);
+            subset[i] = c;
+            upper = c;
+        }
+        subset
+    }
+}
+
+/// All permutations of the numbers below `n`, ranked by their Lehmer code
+/// (i.e., in the factorial number system).  The ranking is lexicographic,
+/// so the identity permutation has hash 0.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Permutations {
+    n: usize,
+}
+
+impl Permutations {
+    /// Create a new hasher for all permutations of `0..n`.
+    pub fn new(n: usize) -> Self {
+        Permutations { n }
+    }
+
+    /// The number of permuted elements.
+    pub fn n(&self) -> usize {
+        self.n
+    }
+}
+
+impl PerfectHash for Permutations {
+    type K = Vec<usize>;
+
+    fn hash(&self, k: Self::K) -> usize {
+        self.hash_equivalent(&k[..])
+    }
+
+    fn hash_ref(&self, k: &Self::K) -> usize {
+        self.hash_equivalent(&k[..])
+    }
+
+    fn size(&self) -> usize {
+        (1..self.n + 1).product()
+    }
+}
+
+impl HashEquivalent<[usize]> for Permutations {
+    fn hash_equivalent(&self, k: &[usize]) -> usize {
+        assert_eq!(self.n, k.len());
+        let mut seen = vec![false; self.n];
+        let mut hash = 0;
+        for (i, &p) in k.iter().enumerate() {
+            assert!(p < self.n && !seen[p], "Not a permutation: {:?}", k);
+            seen[p] = true;
+            /* The Lehmer digit is the number of smaller elements
+             * that are still unused. */
+            let digit = seen[..p].iter().filter(|&&s| !s).count();
+            hash = hash * (self.n - i) + digit;
+        }
+        hash
+    }
+}
+
+impl HashInverse for Permutations {
+    fn invert(&self, hash: usize) -> Self::K {
+        assert!(hash < self.size());
+        let mut digits = vec![0; self.n];
+        let mut rest = hash;
+        for i in (0..self.n).rev() {
+            let radix = self.n - i;
+            digits[i] = rest % radix;
+            rest /= radix;
+        }
+        let mut unused = (0..self.n).collect::<Vec<_>>();
+        digits.into_iter().map(|d| unused.remove(d)).collect()
+    }
+}
+
+impl OrderedHash for Permutations {}
+
+/// Multisets over the numbers below `max_counts.len()`, where element `i`
+/// may occur at most `max_counts[i]` times.  Keys are count vectors,
+/// which are ranked as mixed-radix numbers with the first count being
+/// the most significant digit.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct BoundedMultisets {
+    max_counts: Vec<usize>,
+}
+
+impl BoundedMultisets {
+    /// Create a new hasher for all count vectors that are
+    /// component-wise bounded by `max_counts`.
+    pub fn new(max_counts: Vec<usize>) -> Self {
+        BoundedMultisets { max_counts }
+    }
+
+    /// The maximum count of each element.
+    pub fn max_counts(&self) -> &[usize] {
+        &self.max_counts
+    }
+}
+
+impl PerfectHash for BoundedMultisets {
+    type K = Vec<usize>;
+
+    fn hash(&self, k: Self::K) -> usize {
+        self.hash_equivalent(&k[..])
+    }
+
+    fn hash_ref(&self, k: &Self::K) -> usize {
+        self.hash_equivalent(&k[..])
+    }
+
+    fn size(&self) -> usize {
+        self.max_counts.iter().map(|&max| max + 1).product()
+    }
+}
+
+impl HashEquivalent<[usize]> for BoundedMultisets {
+    fn hash_equivalent(&self, k: &[usize]) -> usize {
+        assert_eq!(self.max_counts.len(), k.len());
+        let mut hash = 0;
+        for (&count, &max) in k.iter().zip(self.max_counts.iter()) {
+            assert!(count <= max, "Count {} exceeds maximum {}", count, max);
+            hash = hash * (max + 1) + count;
+        }
+        hash
+    }
+}
+
+impl HashInverse for BoundedMultisets {
+    fn invert(&self, hash: usize) -> Self::K {
+        assert!(hash < self.size());
+        let mut counts = vec![0; self.max_counts.len()];
+        let mut rest = hash;
+        for (count, &max) in counts.iter_mut().zip(self.max_counts.iter()).rev() {
+            *count = rest % (max + 1);
+            rest /= max + 1;
+        }
+        counts
+    }
+}
+
+impl OrderedHash for BoundedMultisets {}
+
+/// All subsets of a base set of `n` elements, represented as bitmasks.
+/// The hash of a subset is simply its bitmask, so the size is `2^n`.
+///
+/// By default, keys are plain `u32` bitmasks.  Any flags type that can be
+/// converted into (and, for `HashInverse`, from) a `u32` can be used instead.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct PowerSet<F = u32> {
+    n: u32,
+    flags: PhantomData<F>,
+}
+
+impl<F> PowerSet<F> {
+    /// Create a new hasher for all subsets of a base set of `n` elements,
+    /// i.e., all bitmasks of the lowest `n` bits.
+    pub fn new(n: u32) -> Self {
+        assert!(n <= 32 && (n as usize) < 8 * std::mem::size_of::<usize>());
+        PowerSet { n, flags: PhantomData }
+    }
+
+    /// The size of the base set.
+    pub fn n(&self) -> u32 {
+        self.n
+    }
+}
+
+impl<F: Into<u32>> PerfectHash for PowerSet<F> {
+    type K = F;
+
+    fn hash(&self, k: Self::K) -> usize {
+        let bits = k.into() as usize;
+        assert!(bits < self.size(), "Bitmask {:#x} has too many bits", bits);
+        bits
+    }
+
+    fn size(&self) -> usize {
+        1 << self.n
+    }
+}
+
+impl<F: Into<u32> + From<u32>> HashInverse for PowerSet<F> {
+    fn invert(&self, hash: usize) -> Self::K {
+        assert!(hash < self.size());
+        F::from(hash as u32)
+    }
+}
+
+impl OrderedHash for PowerSet<u32> {}
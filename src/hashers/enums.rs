@@ -0,0 +1,167 @@
+// phf_mut – Perfectly hashed mutable containers
+// Copyright (C) 2017  Ben Wiederhake
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+use {PerfectHash, HashInverse, StaticPerfectHash};
+
+/// Types with finitely many values that can be numbered `0..LENGTH`,
+/// typically field-less enums.
+///
+/// With the `enum-map` feature, this is implemented for every type
+/// that implements `enum_map::Enum`, e.g. through `#[derive(Enum)]`.
+/// With the `strum` feature, `enumerable_from_strum!` implements it for
+/// enums that derive `EnumCount` and `FromRepr`.
+/// Otherwise, the implementation is usually a pair of simple `match`es.
+pub trait Enumerable: Sized {
+    /// The number of values.
+    const LENGTH: usize;
+
+    /// The value with the given number, which must be below `LENGTH`.
+    fn from_usize(index: usize) -> Self;
+
+    /// The number of this value, which is below `LENGTH`.
+    fn into_usize(self) -> usize;
+}
+
+#[cfg(feature = "enum-map")]
+impl<E: ::enum_map::Enum> Enumerable for E {
+    const LENGTH: usize = <E as ::enum_map::Enum>::LENGTH;
+
+    fn from_usize(index: usize) -> Self {
+        <E as ::enum_map::Enum>::from_usize(index)
+    }
+
+    fn into_usize(self) -> usize {
+        <E as ::enum_map::Enum>::into_usize(self)
+    }
+}
+
+#[cfg(feature = "strum")]
+#[doc(hidden)]
+pub use strum::EnumCount as __StrumEnumCount;
+
+/// Implement `Enumerable` for field-less enums that derive
+/// `strum::EnumCount` and `strum::FromRepr`, and whose variants have
+/// the default discriminants `0..COUNT`.  Needs the `strum` feature.
+///
+/// ```
+/// #[macro_use]
+/// extern crate phf_mut;
+/// extern crate strum;
+/// use phf_mut::PerfectHash;
+/// use phf_mut::hashers::EnumHash;
+/// use strum::{EnumCount, FromRepr};
+///
+/// #[derive(Clone, Copy, EnumCount, FromRepr)]
+/// enum Direction { North, East, South, West }
+///
+/// enumerable_from_strum!(Direction);
+///
+/// fn main() {
+///     assert_eq!(2, EnumHash::<Direction>::new().hash(Direction::South));
+/// }
+/// ```
+#[cfg(feature = "strum")]
+#[macro_export]
+macro_rules! enumerable_from_strum {
+    ($($t:ty),* $(,)?) => { $(
+        impl $crate::hashers::Enumerable for $t {
+            const LENGTH: usize =
+                <$t as $crate::hashers::__StrumEnumCount>::COUNT;
+
+            fn from_usize(index: usize) -> Self {
+                <$t>::from_repr(index as _).expect("index out of range")
+            }
+
+            fn into_usize(self) -> usize {
+                self as usize
+            }
+        }
+    )* };
+}
+
+/// All values of an `Enumerable` type, usually a field-less enum.
+/// The hash of a value is its number.
+pub struct EnumHash<E> {
+    values: PhantomData<E>,
+}
+
+impl<E> EnumHash<E> {
+    /// Create a new hasher for all values of `E`.
+    pub const fn new() -> Self {
+        EnumHash { values: PhantomData }
+    }
+}
+
+/* The hasher holds no `E`, so none of these need any bounds on it,
+ * unlike their derived counterparts. */
+impl<E> Default for EnumHash<E> {
+    fn default() -> Self {
+        EnumHash::new()
+    }
+}
+
+impl<E> Clone for EnumHash<E> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<E> Copy for EnumHash<E> {}
+
+impl<E> PartialEq for EnumHash<E> {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+impl<E> Eq for EnumHash<E> {}
+
+impl<E> Hash for EnumHash<E> {
+    fn hash<S: Hasher>(&self, _: &mut S) {}
+}
+
+impl<E> fmt::Debug for EnumHash<E> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "EnumHash<{}>", std::any::type_name::<E>())
+    }
+}
+
+impl<E: Enumerable> PerfectHash for EnumHash<E> {
+    type K = E;
+
+    fn hash(&self, k: Self::K) -> usize {
+        k.into_usize()
+    }
+
+    fn size(&self) -> usize {
+        E::LENGTH
+    }
+}
+
+impl<E: Enumerable> HashInverse for EnumHash<E> {
+    fn invert(&self, hash: usize) -> Self::K {
+        assert!(hash < E::LENGTH);
+        E::from_usize(hash)
+    }
+}
+
+impl<E: Enumerable> StaticPerfectHash for EnumHash<E> {
+    const SIZE: usize = E::LENGTH;
+}
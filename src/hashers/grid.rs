@@ -0,0 +1,673 @@
+// phf_mut – Perfectly hashed mutable containers
+// Copyright (C) 2017  Ben Wiederhake
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+
+use {PerfectHash, HashInverse, Map, Storage};
+
+/// Offsets of the 4-neighborhood (von Neumann neighborhood) in 2D.
+const NEIGHBORS_4: [(isize, isize); 4] = [(0, -1), (-1, 0), (1, 0), (0, 1)];
+
+/// Offsets of the 8-neighborhood (Moore neighborhood) in 2D.
+const NEIGHBORS_8: [(isize, isize); 8] = [
+    (-1, -1), (0, -1), (1, -1),
+    (-1, 0), (1, 0),
+    (-1, 1), (0, 1), (1, 1),
+];
+
+/// Offsets of the 6-neighborhood (von Neumann neighborhood) in 3D.
+const NEIGHBORS_6: [(isize, isize, isize); 6] = [
+    (0, 0, -1), (0, -1, 0), (-1, 0, 0), (1, 0, 0), (0, 1, 0), (0, 0, 1),
+];
+
+/// Offsets of the 26-neighborhood (Moore neighborhood) in 3D.
+const NEIGHBORS_26: [(isize, isize, isize); 26] = [
+    (-1, -1, -1), (0, -1, -1), (1, -1, -1),
+    (-1, 0, -1), (0, 0, -1), (1, 0, -1),
+    (-1, 1, -1), (0, 1, -1), (1, 1, -1),
+    (-1, -1, 0), (0, -1, 0), (1, -1, 0),
+    (-1, 0, 0), (1, 0, 0),
+    (-1, 1, 0), (0, 1, 0), (1, 1, 0),
+    (-1, -1, 1), (0, -1, 1), (1, -1, 1),
+    (-1, 0, 1), (0, 0, 1), (1, 0, 1),
+    (-1, 1, 1), (0, 1, 1), (1, 1, 1),
+];
+
+/// Add a signed offset to a coordinate, if the result is below `bound`.
+fn offset(coord: usize, delta: isize, bound: usize) -> Option<usize> {
+    let moved = if delta < 0 {
+        coord.checked_sub(delta.unsigned_abs())
+    } else {
+        coord.checked_add(delta as usize)
+    };
+    moved.filter(|&c| c < bound)
+}
+
+/// A bounded two-dimensional grid of `w * h` cells, keyed by `(x, y)`.
+/// Cells are stored row by row, i.e., `x` is the "minor" coordinate.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Grid2 {
+    w: usize,
+    h: usize,
+}
+
+impl Grid2 {
+    /// Create a new hasher for a grid of width `w` and height `h`.
+    pub fn new(w: usize, h: usize) -> Self {
+        Grid2 { w, h }
+    }
+
+    /// The width of the grid, i.e., the exclusive upper bound of `x`.
+    pub fn w(&self) -> usize {
+        self.w
+    }
+
+    /// The height of the grid, i.e., the exclusive upper bound of `y`.
+    pub fn h(&self) -> usize {
+        self.h
+    }
+
+    /// Returns whether the key lies within the grid.
+    pub fn contains(&self, (x, y): (usize, usize)) -> bool {
+        x < self.w && y < self.h
+    }
+
+    /// Iterate over the valid 4-neighborhood of a key, i.e.,
+    /// all horizontally and vertically adjacent cells within the grid.
+    pub fn neighbors4(&self, k: (usize, usize)) -> Grid2Neighbors {
+        self.neighbors(k, &NEIGHBORS_4)
+    }
+
+    /// Iterate over the valid 8-neighborhood of a key, i.e.,
+    /// all horizontally, vertically and diagonally adjacent cells
+    /// within the grid.
+    pub fn neighbors8(&self, k: (usize, usize)) -> Grid2Neighbors {
+        self.neighbors(k, &NEIGHBORS_8)
+    }
+
+    fn neighbors(&self, k: (usize, usize),
+                 offsets: &'static [(isize, isize)]) -> Grid2Neighbors {
+        assert!(self.contains(k));
+        Grid2Neighbors { grid: *self, center: k, offsets }
+    }
+}
+
+impl PerfectHash for Grid2 {
+    type K = (usize, usize);
+
+    fn hash(&self, (x, y): Self::K) -> usize {
+        assert!(self.contains((x, y)));
+        y * self.w + x
+    }
+
+    fn size(&self) -> usize {
+        self.w * self.h
+    }
+}
+
+impl HashInverse for Grid2 {
+    fn invert(&self, hash: usize) -> Self::K {
+        assert!(hash < self.size());
+        (hash % self.w, hash / self.w)
+    }
+}
+
+/// Iterator over the neighbors of a cell in a `Grid2`.
+/// See `Grid2::neighbors4` and `Grid2::neighbors8`.
+#[derive(Clone, Debug)]
+pub struct Grid2Neighbors {
+    grid: Grid2,
+    center: (usize, usize),
+    offsets: &'static [(isize, isize)],
+}
+
+impl Iterator for Grid2Neighbors {
+    type Item = (usize, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((&(dx, dy), rest)) = self.offsets.split_first() {
+            self.offsets = rest;
+            let (x, y) = self.center;
+            let neighbor = offset(x, dx, self.grid.w)
+                .and_then(|x| offset(y, dy, self.grid.h).map(|y| (x, y)));
+            if neighbor.is_some() {
+                return neighbor;
+            }
+        }
+        None
+    }
+}
+
+/// How `Map::stencil` treats neighbors beyond the border of a `Grid2`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum StencilEdge {
+    /// Use the nearest cell within the grid instead, i.e., repeat the border.
+    Clamp,
+    /// Use the cell on the opposite side of the grid, like `Torus2` does.
+    Wrap,
+}
+
+impl StencilEdge {
+    /// The coordinates before, at and after `c` along an axis of length `n`.
+    fn around(self, c: usize, n: usize) -> [usize; 3] {
+        match self {
+            StencilEdge::Clamp => [c.saturating_sub(1), c, (c + 1).min(n - 1)],
+            StencilEdge::Wrap => [(c + n - 1) % n, c, (c + 1) % n],
+        }
+    }
+}
+
+impl<V, S: Storage<V>> Map<V, Grid2, S> {
+    /// Create an iterator over all cells, in hash order, that also yields
+    /// the 3x3 neighborhood of each cell, indexed as `[dy + 1][dx + 1]`.
+    /// `edge` determines which values stand in for the neighbors beyond
+    /// the border.  This is the core loop of cellular automata and blurs.
+    pub fn stencil(&self, edge: StencilEdge) -> Stencil<'_, V> {
+        let grid = self.hasher();
+        Stencil { values: self.as_slice(), w: grid.w, h: grid.h, edge, pos: 0 }
+    }
+}
+
+/// Iterator over the cells of a `Map` keyed by `Grid2`, together with
+/// their neighborhoods.  See `Map::stencil`.
+#[derive(Clone, Debug)]
+pub struct Stencil<'a, V: 'a> {
+    values: &'a [V],
+    w: usize,
+    h: usize,
+    edge: StencilEdge,
+    pos: usize,
+}
+
+impl<'a, V: 'a> Iterator for Stencil<'a, V> {
+    type Item = ((usize, usize), [[&'a V; 3]; 3]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.pos >= self.values.len() {
+            return None;
+        }
+        let (x, y) = (self.pos % self.w, self.pos / self.w);
+        self.pos += 1;
+        let xs = self.edge.around(x, self.w);
+        let (values, w) = (self.values, self.w);
+        let rows = self.edge.around(y, self.h)
+            .map(|y| &values[y * w..(y + 1) * w]);
+        Some(((x, y), rows.map(|row| xs.map(|x| &row[x]))))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let rest = self.values.len() - self.pos;
+        (rest, Some(rest))
+    }
+}
+
+impl<'a, V: 'a> ExactSizeIterator for Stencil<'a, V> {}
+
+/// A bounded three-dimensional grid of `w * h * d` cells,
+/// keyed by `(x, y, z)`.  Cells are stored layer by layer and row by row,
+/// i.e., `x` is the "minor" and `z` the "major" coordinate.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Grid3 {
+    w: usize,
+    h: usize,
+    d: usize,
+}
+
+impl Grid3 {
+    /// Create a new hasher for a grid of width `w`, height `h` and depth `d`.
+    pub fn new(w: usize, h: usize, d: usize) -> Self {
+        Grid3 { w, h, d }
+    }
+
+    /// The width of the grid, i.e., the exclusive upper bound of `x`.
+    pub fn w(&self) -> usize {
+        self.w
+    }
+
+    /// The height of the grid, i.e., the exclusive upper bound of `y`.
+    pub fn h(&self) -> usize {
+        self.h
+    }
+
+    /// The depth of the grid, i.e., the exclusive upper bound of `z`.
+    pub fn d(&self) -> usize {
+        self.d
+    }
+
+    /// Returns whether the key lies within the grid.
+    pub fn contains(&self, (x, y, z): (usize, usize, usize)) -> bool {
+        x < self.w && y < self.h && z < self.d
+    }
+
+    /// Iterate over the valid 6-neighborhood of a key, i.e.,
+    /// all cells within the grid that share a face with it.
+    pub fn neighbors6(&self, k: (usize, usize, usize)) -> Grid3Neighbors {
+        self.neighbors(k, &NEIGHBORS_6)
+    }
+
+    /// Iterate over the valid 26-neighborhood of a key, i.e.,
+    /// all cells within the grid that share a face, edge or corner with it.
+    pub fn neighbors26(&self, k: (usize, usize, usize)) -> Grid3Neighbors {
+        self.neighbors(k, &NEIGHBORS_26)
+    }
+
+    fn neighbors(&self, k: (usize, usize, usize),
+                 offsets: &'static [(isize, isize, isize)]) -> Grid3Neighbors {
+        assert!(self.contains(k));
+        Grid3Neighbors { grid: *self, center: k, offsets }
+    }
+}
+
+impl PerfectHash for Grid3 {
+    type K = (usize, usize, usize);
+
+    fn hash(&self, (x, y, z): Self::K) -> usize {
+        assert!(self.contains((x, y, z)));
+        (z * self.h + y) * self.w + x
+    }
+
+    fn size(&self) -> usize {
+        self.w * self.h * self.d
+    }
+}
+
+impl HashInverse for Grid3 {
+    fn invert(&self, hash: usize) -> Self::K {
+        assert!(hash < self.size());
+        let rest = hash / self.w;
+        (hash % self.w, rest % self.h, rest / self.h)
+    }
+}
+
+/// Iterator over the neighbors of a cell in a `Grid3`.
+/// See `Grid3::neighbors6` and `Grid3::neighbors26`.
+#[derive(Clone, Debug)]
+pub struct Grid3Neighbors {
+    grid: Grid3,
+    center: (usize, usize, usize),
+    offsets: &'static [(isize, isize, isize)],
+}
+
+impl Iterator for Grid3Neighbors {
+    type Item = (usize, usize, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((&(dx, dy, dz), rest)) = self.offsets.split_first() {
+            self.offsets = rest;
+            let (x, y, z) = self.center;
+            let neighbor = offset(x, dx, self.grid.w).and_then(|x| {
+                offset(y, dy, self.grid.h)
+                    .and_then(|y| offset(z, dz, self.grid.d).map(|z| (x, y, z)))
+            });
+            if neighbor.is_some() {
+                return neighbor;
+            }
+        }
+        None
+    }
+}
+
+/// A wrapping two-dimensional grid of `w * h` cells, keyed by `(x, y)`,
+/// where coordinates are taken modulo the width and height respectively.
+/// This way, raw coordinates from periodic-boundary simulations
+/// (even negative ones) can be used directly.
+/// Inversion always yields the canonical coordinates in `0..w` and `0..h`.
+/// Cells are stored row by row, like in `Grid2`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Torus2 {
+    w: usize,
+    h: usize,
+}
+
+impl Torus2 {
+    /// Create a new hasher for a wrapping grid of width `w` and height `h`.
+    pub fn new(w: usize, h: usize) -> Self {
+        assert!(w > 0 && h > 0);
+        assert!(w <= isize::MAX as usize && h <= isize::MAX as usize);
+        Torus2 { w, h }
+    }
+
+    /// The width of the grid, i.e., the period of `x`.
+    pub fn w(&self) -> usize {
+        self.w
+    }
+
+    /// The height of the grid, i.e., the period of `y`.
+    pub fn h(&self) -> usize {
+        self.h
+    }
+
+    /// Reduce a key to its canonical coordinates.
+    pub fn wrap(&self, (x, y): (isize, isize)) -> (isize, isize) {
+        (x.rem_euclid(self.w as isize), y.rem_euclid(self.h as isize))
+    }
+
+    /// Iterate over the 4-neighborhood of a key, in canonical coordinates.
+    /// On very small grids, a neighbor may be yielded more than once,
+    /// or be the key itself.
+    pub fn neighbors4(&self, k: (isize, isize)) -> Torus2Neighbors {
+        self.neighbors(k, &NEIGHBORS_4)
+    }
+
+    /// Iterate over the 8-neighborhood of a key, in canonical coordinates.
+    /// On very small grids, a neighbor may be yielded more than once,
+    /// or be the key itself.
+    pub fn neighbors8(&self, k: (isize, isize)) -> Torus2Neighbors {
+        self.neighbors(k, &NEIGHBORS_8)
+    }
+
+    fn neighbors(&self, k: (isize, isize),
+                 offsets: &'static [(isize, isize)]) -> Torus2Neighbors {
+        Torus2Neighbors { grid: *self, center: self.wrap(k), offsets }
+    }
+}
+
+impl PerfectHash for Torus2 {
+    type K = (isize, isize);
+
+    fn hash(&self, k: Self::K) -> usize {
+        let (x, y) = self.wrap(k);
+        y as usize * self.w + x as usize
+    }
+
+    fn size(&self) -> usize {
+        self.w * self.h
+    }
+}
+
+impl HashInverse for Torus2 {
+    fn invert(&self, hash: usize) -> Self::K {
+        assert!(hash < self.size());
+        ((hash % self.w) as isize, (hash / self.w) as isize)
+    }
+}
+
+/// Iterator over the neighbors of a cell in a `Torus2`.
+/// See `Torus2::neighbors4` and `Torus2::neighbors8`.
+#[derive(Clone, Debug)]
+pub struct Torus2Neighbors {
+    grid: Torus2,
+    center: (isize, isize),
+    offsets: &'static [(isize, isize)],
+}
+
+impl Iterator for Torus2Neighbors {
+    type Item = (isize, isize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.offsets.split_first().map(|(&(dx, dy), rest)| {
+            self.offsets = rest;
+            let (x, y) = self.center;
+            self.grid.wrap((x + dx, y + dy))
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.offsets.len(), Some(self.offsets.len()))
+    }
+}
+
+impl ExactSizeIterator for Torus2Neighbors {}
+
+/// Offsets of the 6 neighbors of a hex cell, in axial coordinates.
+const NEIGHBORS_HEX: [(isize, isize); 6] =
+    [(1, 0), (1, -1), (0, -1), (-1, 0), (-1, 1), (0, 1)];
+
+/// A hexagon-shaped board of hex cells with the given `radius`,
+/// keyed by axial coordinates `(q, r)`.  The center cell is `(0, 0)`,
+/// and a cell belongs to the board iff `|q|`, `|r|` and `|q + r|`
+/// are all at most `radius`.  Cells are stored row by row,
+/// i.e., ordered by `r` first and by `q` second.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct HexGrid {
+    radius: usize,
+}
+
+impl HexGrid {
+    /// Create a new hasher for a hexagonal board with the given `radius`.
+    /// A radius of 0 means a single cell.
+    pub fn new(radius: usize) -> Self {
+        assert!(radius < isize::MAX as usize / 2);
+        HexGrid { radius }
+    }
+
+    /// The radius of the board.
+    pub fn radius(&self) -> usize {
+        self.radius
+    }
+
+    /// Returns whether the key lies within the board.
+    pub fn contains(&self, (q, r): (isize, isize)) -> bool {
+        let radius = self.radius as isize;
+        q.abs() <= radius && r.abs() <= radius && (q + r).abs() <= radius
+    }
+
+    /// Iterate over the valid hex-neighborhood of a key,
+    /// i.e., all adjacent cells within the board.
+    pub fn neighbors(&self, k: (isize, isize)) -> HexNeighbors {
+        assert!(self.contains(k));
+        HexNeighbors { grid: *self, center: k, offsets: &NEIGHBORS_HEX }
+    }
+
+    /// Number of cells in all rows before the `row`-th one,
+    /// counting rows from 0 (i.e., `r == -radius`).
+    fn row_offset(&self, row: usize) -> usize {
+        let radius = self.radius;
+        if row <= radius {
+            row * (radius + 1) + row * row.saturating_sub(1) / 2
+        } else {
+            let below = row - radius;
+            self.row_offset(radius) + below * (2 * radius + 1)
+                - below * (below - 1) / 2
+        }
+    }
+}
+
+impl PerfectHash for HexGrid {
+    type K = (isize, isize);
+
+    fn hash(&self, (q, r): Self::K) -> usize {
+        assert!(self.contains((q, r)));
+        let radius = self.radius as isize;
+        let first_q = std::cmp::max(-radius, -radius - r);
+        self.row_offset((r + radius) as usize) + (q - first_q) as usize
+    }
+
+    fn size(&self) -> usize {
+        3 * self.radius * (self.radius + 1) + 1
+    }
+}
+
+impl HashInverse for HexGrid {
+    fn invert(&self, hash: usize) -> Self::K {
+        assert!(hash < self.size());
+        /* Binary search for the last row starting at or before 'hash'. */
+        let (mut lo, mut hi) = (0, 2 * self.radius + 1);
+        while hi - lo > 1 {
+            let mid = lo + (hi - lo) / 2;
+            if self.row_offset(mid) <= hash {
+                lo = mid;
+            } else {
+                hi = mid;
+            }
+        }
+        let radius = self.radius as isize;
+        let r = lo as isize - radius;
+        let first_q = std::cmp::max(-radius, -radius - r);
+        (first_q + (hash - self.row_offset(lo)) as isize, r)
+    }
+}
+
+/// Iterator over the neighbors of a cell in a `HexGrid`.
+/// See `HexGrid::neighbors`.
+#[derive(Clone, Debug)]
+pub struct HexNeighbors {
+    grid: HexGrid,
+    center: (isize, isize),
+    offsets: &'static [(isize, isize)],
+}
+
+impl Iterator for HexNeighbors {
+    type Item = (isize, isize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((&(dq, dr), rest)) = self.offsets.split_first() {
+            self.offsets = rest;
+            let (q, r) = self.center;
+            let neighbor = (q + dq, r + dr);
+            if self.grid.contains(neighbor) {
+                return Some(neighbor);
+            }
+        }
+        None
+    }
+}
+
+/// Two-dimensional grid hashers, whose keys correspond to coordinates
+/// `(x, y)` in `0..w` and `0..h`.  This is what `ZOrder` builds upon.
+pub trait Planar: PerfectHash {
+    /// The width and height of the grid.
+    fn dims(&self) -> (usize, usize);
+
+    /// The canonical coordinates of a key.
+    fn coords(&self, k: Self::K) -> (usize, usize);
+
+    /// The key for some canonical coordinates.
+    fn key(&self, x: usize, y: usize) -> Self::K;
+}
+
+impl Planar for Grid2 {
+    fn dims(&self) -> (usize, usize) {
+        (self.w, self.h)
+    }
+
+    fn coords(&self, k: Self::K) -> (usize, usize) {
+        assert!(self.contains(k));
+        k
+    }
+
+    fn key(&self, x: usize, y: usize) -> Self::K {
+        (x, y)
+    }
+}
+
+impl Planar for Torus2 {
+    fn dims(&self) -> (usize, usize) {
+        (self.w, self.h)
+    }
+
+    fn coords(&self, k: Self::K) -> (usize, usize) {
+        let (x, y) = self.wrap(k);
+        (x as usize, y as usize)
+    }
+
+    fn key(&self, x: usize, y: usize) -> Self::K {
+        (x as isize, y as isize)
+    }
+}
+
+/// Spread the lower 32 bits of `v` to the even bit positions.
+fn spread(v: u64) -> u64 {
+    let mut v = v & 0xFFFF_FFFF;
+    v = (v | (v << 16)) & 0x0000_FFFF_0000_FFFF;
+    v = (v | (v << 8)) & 0x00FF_00FF_00FF_00FF;
+    v = (v | (v << 4)) & 0x0F0F_0F0F_0F0F_0F0F;
+    v = (v | (v << 2)) & 0x3333_3333_3333_3333;
+    (v | (v << 1)) & 0x5555_5555_5555_5555
+}
+
+/// Inverse of `spread`: gather the even bit positions into the lower 32 bits.
+fn gather(v: u64) -> u64 {
+    let mut v = v & 0x5555_5555_5555_5555;
+    v = (v | (v >> 1)) & 0x3333_3333_3333_3333;
+    v = (v | (v >> 2)) & 0x0F0F_0F0F_0F0F_0F0F;
+    v = (v | (v >> 4)) & 0x00FF_00FF_00FF_00FF;
+    v = (v | (v >> 8)) & 0x0000_FFFF_0000_FFFF;
+    (v | (v >> 16)) & 0xFFFF_FFFF
+}
+
+/// Adapter that lays out the cells of a two-dimensional grid hasher
+/// in Morton order (Z-order) instead of row by row.
+/// Cells that are close to each other in the grid then tend to be
+/// close to each other in the backing storage, too.
+///
+/// Both the width and the height must be powers of two.  If they differ,
+/// the excess bits of the longer side simply become the most significant
+/// bits of the index, so the hash stays minimal.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ZOrder<H> {
+    inner: H,
+    /// Number of bits of each coordinate that get interleaved.
+    shared_bits: u32,
+    /// Whether the excess bits belong to `x` (otherwise to `y`).
+    x_longer: bool,
+}
+
+impl<H: Planar> ZOrder<H> {
+    /// Wrap a grid hasher, whose dimensions must be powers of two.
+    pub fn new(inner: H) -> Self {
+        let (w, h) = inner.dims();
+        assert!(w.is_power_of_two() && h.is_power_of_two(),
+                "Dimensions {}x{} are not powers of two", w, h);
+        let shared_bits = std::cmp::min(w, h).trailing_zeros();
+        assert!(shared_bits < 32);
+        ZOrder { inner, shared_bits, x_longer: w > h }
+    }
+}
+
+impl<H> ZOrder<H> {
+    /// The wrapped, row-by-row grid hasher.
+    pub fn inner(&self) -> &H {
+        &self.inner
+    }
+
+    /// Unwrap the row-by-row grid hasher.
+    pub fn into_inner(self) -> H {
+        self.inner
+    }
+}
+
+impl<H: Planar> PerfectHash for ZOrder<H> {
+    type K = H::K;
+
+    fn hash(&self, k: Self::K) -> usize {
+        let (x, y) = self.inner.coords(k);
+        let mask = (1u64 << self.shared_bits) - 1;
+        let (x, y) = (x as u64, y as u64);
+        let low = spread(x & mask) | (spread(y & mask) << 1);
+        let high = (x | y) >> self.shared_bits;
+        ((high << (2 * self.shared_bits)) | low) as usize
+    }
+
+    fn size(&self) -> usize {
+        self.inner.size()
+    }
+}
+
+impl<H: Planar + HashInverse> HashInverse for ZOrder<H> {
+    fn invert(&self, hash: usize) -> Self::K {
+        assert!(hash < self.size());
+        let hash = hash as u64;
+        let low = hash & ((1u64 << (2 * self.shared_bits)) - 1);
+        let high = (hash >> (2 * self.shared_bits)) << self.shared_bits;
+        let (mut x, mut y) = (gather(low), gather(low >> 1));
+        if self.x_longer {
+            x |= high;
+        } else {
+            y |= high;
+        }
+        self.inner.key(x as usize, y as usize)
+    }
+}
@@ -0,0 +1,54 @@
+// phf_mut – Perfectly hashed mutable containers
+// Copyright (C) 2017  Ben Wiederhake
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+
+//! Ready-made perfect hash functions for common domains.
+
+mod bio;
+#[cfg(feature = "chrono")]
+mod calendar;
+mod cards;
+mod chess;
+mod clock;
+mod combinatorics;
+mod enums;
+mod grid;
+mod music;
+mod net;
+mod pairs;
+mod sudoku;
+mod text;
+
+pub use self::bio::{AminoAcids, Codons, Kmer};
+#[cfg(feature = "chrono")]
+pub use self::calendar::{DaysOfYear, Months, Weekdays};
+pub use self::cards::{Card, Cards, CardsWithJokers, Rank, Suit};
+pub use self::chess::{Color, Piece, PieceKind, PieceSquares, Pieces, Square,
+                      Squares};
+pub use self::clock::TimeOfDay;
+pub use self::combinatorics::{BoundedMultisets, Combinations, Permutations, PowerSet};
+pub use self::enums::{EnumHash, Enumerable};
+#[cfg(feature = "strum")]
+#[doc(hidden)]
+pub use self::enums::__StrumEnumCount;
+pub use self::grid::{Grid2, Grid2Neighbors, Grid3, Grid3Neighbors, HexGrid,
+                     HexNeighbors, Planar, Stencil, StencilEdge, Torus2,
+                     Torus2Neighbors, ZOrder};
+pub use self::music::MidiNotes;
+pub use self::net::Ipv4Prefix;
+pub use self::pairs::{OrderedPairs, UnorderedPairs};
+pub use self::sudoku::Sudoku;
+pub use self::text::CharRange;
@@ -0,0 +1,55 @@
+// phf_mut – Perfectly hashed mutable containers
+// Copyright (C) 2017  Ben Wiederhake
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+
+use {PerfectHash, HashInverse, OrderedHash, StaticPerfectHash};
+
+/// The 128 MIDI note numbers, keyed by `(octave, pitch_class)`.
+/// The pitch class counts semitones from C, i.e., C is 0 and B is 11.
+/// Octaves follow the usual MIDI convention, where middle C (note 60)
+/// is in octave 4, so note 0 is C in octave -1, and note 127 is G9.
+/// The hash of a key is its MIDI note number.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct MidiNotes;
+
+impl PerfectHash for MidiNotes {
+    type K = (i8, u8);
+
+    fn hash(&self, (octave, pitch_class): Self::K) -> usize {
+        assert!(pitch_class < 12, "Invalid pitch class {}", pitch_class);
+        let note = (octave as isize + 1) * 12 + pitch_class as isize;
+        assert!((0..128).contains(&note), "Note {:?} is out of MIDI range",
+                (octave, pitch_class));
+        note as usize
+    }
+
+    fn size(&self) -> usize {
+        128
+    }
+}
+
+impl HashInverse for MidiNotes {
+    fn invert(&self, hash: usize) -> Self::K {
+        assert!(hash < 128);
+        ((hash / 12) as i8 - 1, (hash % 12) as u8)
+    }
+}
+
+impl StaticPerfectHash for MidiNotes {
+    const SIZE: usize = 128;
+}
+
+impl OrderedHash for MidiNotes {}
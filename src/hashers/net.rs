@@ -0,0 +1,84 @@
+// phf_mut – Perfectly hashed mutable containers
+// Copyright (C) 2017  Ben Wiederhake
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+
+use std::net::Ipv4Addr;
+use {PerfectHash, HashInverse, OrderedHash};
+
+/// All IPv4 addresses within a CIDR prefix, e.g. `192.168.0.0/16`.
+/// Addresses are laid out in numerical order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Ipv4Prefix {
+    network: u32,
+    len: u8,
+}
+
+impl Ipv4Prefix {
+    /// Create a new hasher for all addresses that share the first `len` bits
+    /// with `addr`.  Host bits of `addr` are ignored.
+    pub fn new(addr: Ipv4Addr, len: u8) -> Self {
+        assert!(len <= 32, "Invalid prefix length {}", len);
+        assert!(((32 - len) as usize) < 8 * std::mem::size_of::<usize>(),
+                "Prefix /{} is too large for this platform", len);
+        let network = u32::from(addr) & Self::mask(len);
+        Ipv4Prefix { network, len }
+    }
+
+    /// The network address, i.e., the first address within the prefix.
+    pub fn network(&self) -> Ipv4Addr {
+        Ipv4Addr::from(self.network)
+    }
+
+    /// The prefix length in bits.
+    pub fn prefix_len(&self) -> u8 {
+        self.len
+    }
+
+    /// Returns whether the address lies within the prefix.
+    pub fn contains(&self, addr: Ipv4Addr) -> bool {
+        u32::from(addr) & Self::mask(self.len) == self.network
+    }
+
+    fn mask(len: u8) -> u32 {
+        (!0u64 << (32 - len)) as u32
+    }
+}
+
+impl PerfectHash for Ipv4Prefix {
+    type K = Ipv4Addr;
+
+    fn hash(&self, k: Self::K) -> usize {
+        assert!(self.contains(k), "{} is not in {}/{}", k, self.network(), self.len);
+        (u32::from(k) - self.network) as usize
+    }
+
+    fn hash_ref(&self, k: &Self::K) -> usize {
+        self.hash(*k)
+    }
+
+    fn size(&self) -> usize {
+        1 << (32 - self.len)
+    }
+}
+
+impl HashInverse for Ipv4Prefix {
+    fn invert(&self, hash: usize) -> Self::K {
+        assert!(hash < self.size());
+        Ipv4Addr::from(self.network + hash as u32)
+    }
+}
+
+impl OrderedHash for Ipv4Prefix {}
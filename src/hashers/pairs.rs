@@ -0,0 +1,151 @@
+// phf_mut – Perfectly hashed mutable containers
+// Copyright (C) 2017  Ben Wiederhake
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+
+use {PerfectHash, HashInverse, OrderedHash};
+
+/// Number of unordered pairs `(a, b)` with `a <= b < n`,
+/// i.e., the `n`-th triangular number.
+fn triangle(n: usize) -> usize {
+    (n + 1) * n / 2
+}
+
+/// Unordered pairs `(a, b)` of numbers below `n`, including the "diagonal"
+/// `(a, a)`.  Both `(a, b)` and `(b, a)` are mapped to the same slot.
+/// Inversion always yields the sorted pair.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct UnorderedPairs {
+    n: usize,
+}
+
+impl UnorderedPairs {
+    /// Create a new hasher for unordered pairs of numbers below `n`.
+    pub fn new(n: usize) -> Self {
+        UnorderedPairs { n }
+    }
+
+    /// The exclusive upper bound of each component.
+    pub fn n(&self) -> usize {
+        self.n
+    }
+
+    fn sort((u, v): (usize, usize)) -> (usize, usize) {
+        if u > v {
+            (v, u)
+        } else {
+            (u, v)
+        }
+    }
+}
+
+impl PerfectHash for UnorderedPairs {
+    type K = (usize, usize);
+
+    fn hash(&self, k: Self::K) -> usize {
+        let (a, b) = Self::sort(k);
+        a + triangle(b)
+    }
+
+    fn size(&self) -> usize {
+        triangle(self.n)
+    }
+}
+
+impl HashInverse for UnorderedPairs {
+    fn invert(&self, hash: usize) -> Self::K {
+        assert!(hash < self.size());
+        /* Largest 'b' with triangle(b) <= hash, by solving the quadratic.
+         * Computed in u128 so that '8 * hash + 1' can't overflow. */
+        let b = (((8 * hash as u128 + 1).isqrt() - 1) / 2) as usize;
+        let a = hash - triangle(b);
+        debug_assert!(a <= b);
+        (a, b)
+    }
+}
+
+/// Ordered pairs `(i, j)` of numbers below `n`, so `(i, j)` and `(j, i)`
+/// are distinct keys.  Useful e.g. for adjacency matrices of directed graphs.
+/// Optionally, the diagonal `(i, i)` can be excluded from the domain.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct OrderedPairs {
+    n: usize,
+    diagonal: bool,
+}
+
+impl OrderedPairs {
+    /// Create a new hasher for all `n * n` ordered pairs of numbers below `n`.
+    pub fn new(n: usize) -> Self {
+        OrderedPairs { n, diagonal: true }
+    }
+
+    /// Create a new hasher for the `n * (n - 1)` ordered pairs
+    /// of *distinct* numbers below `n`.
+    /// Hashing a pair `(i, i)` panics.
+    pub fn without_diagonal(n: usize) -> Self {
+        OrderedPairs { n, diagonal: false }
+    }
+
+    /// The exclusive upper bound of each component.
+    pub fn n(&self) -> usize {
+        self.n
+    }
+
+    /// Whether pairs `(i, i)` are part of the domain.
+    pub fn has_diagonal(&self) -> bool {
+        self.diagonal
+    }
+
+    fn row_len(&self) -> usize {
+        if self.diagonal {
+            self.n
+        } else {
+            self.n.saturating_sub(1)
+        }
+    }
+}
+
+impl PerfectHash for OrderedPairs {
+    type K = (usize, usize);
+
+    fn hash(&self, (i, j): Self::K) -> usize {
+        assert!(i < self.n && j < self.n);
+        if self.diagonal || j < i {
+            i * self.row_len() + j
+        } else {
+            assert!(i != j, "Pair ({}, {}) is on the excluded diagonal.", i, j);
+            i * self.row_len() + j - 1
+        }
+    }
+
+    fn size(&self) -> usize {
+        self.n * self.row_len()
+    }
+}
+
+impl HashInverse for OrderedPairs {
+    fn invert(&self, hash: usize) -> Self::K {
+        assert!(hash < self.size());
+        let row_len = self.row_len();
+        let (i, j) = (hash / row_len, hash % row_len);
+        if self.diagonal || j < i {
+            (i, j)
+        } else {
+            (i, j + 1)
+        }
+    }
+}
+
+impl OrderedHash for OrderedPairs {}
@@ -0,0 +1,107 @@
+// phf_mut – Perfectly hashed mutable containers
+// Copyright (C) 2017  Ben Wiederhake
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+
+use {PerfectHash, HashInverse, OrderedHash};
+
+/// The cells of an `n * n` sudoku-like board, keyed by `(row, col)`,
+/// where the board is divided into boxes of `box_h` rows and `box_w` columns,
+/// and `n = box_w * box_h`.  Cells are stored row by row.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Sudoku {
+    box_w: usize,
+    box_h: usize,
+}
+
+impl Sudoku {
+    /// Create a new hasher for a board with square boxes,
+    /// e.g. `Sudoku::new(3)` for the classic 9x9 board.
+    pub fn new(box_size: usize) -> Self {
+        Sudoku::with_boxes(box_size, box_size)
+    }
+
+    /// Create a new hasher for a board with rectangular boxes
+    /// of `box_w` columns and `box_h` rows,
+    /// e.g. `Sudoku::with_boxes(3, 2)` for a 6x6 board.
+    pub fn with_boxes(box_w: usize, box_h: usize) -> Self {
+        Sudoku { box_w, box_h }
+    }
+
+    /// The number of rows (and columns, and boxes) of the board.
+    pub fn n(&self) -> usize {
+        self.box_w * self.box_h
+    }
+
+    /// The width of each box.
+    pub fn box_w(&self) -> usize {
+        self.box_w
+    }
+
+    /// The height of each box.
+    pub fn box_h(&self) -> usize {
+        self.box_h
+    }
+
+    /// Returns whether the key lies within the board.
+    pub fn contains(&self, (row, col): (usize, usize)) -> bool {
+        row < self.n() && col < self.n()
+    }
+
+    /// The index of the box a cell is in, counting row by row.
+    pub fn box_of(&self, (row, col): (usize, usize)) -> usize {
+        assert!(self.contains((row, col)));
+        (row / self.box_h) * self.box_h + col / self.box_w
+    }
+
+    /// All peers of a cell, i.e., all other cells that share its row,
+    /// column or box.  The peers are returned in hash order.
+    pub fn peers(&self, k: (usize, usize)) -> Vec<(usize, usize)> {
+        let (row, col) = k;
+        let home = self.box_of(k);
+        let mut peers = Vec::with_capacity(3 * self.n());
+        for r in 0..self.n() {
+            for c in 0..self.n() {
+                let is_peer = r == row || c == col || self.box_of((r, c)) == home;
+                if is_peer && (r, c) != k {
+                    peers.push((r, c));
+                }
+            }
+        }
+        peers
+    }
+}
+
+impl PerfectHash for Sudoku {
+    type K = (usize, usize);
+
+    fn hash(&self, (row, col): Self::K) -> usize {
+        assert!(self.contains((row, col)));
+        row * self.n() + col
+    }
+
+    fn size(&self) -> usize {
+        self.n() * self.n()
+    }
+}
+
+impl HashInverse for Sudoku {
+    fn invert(&self, hash: usize) -> Self::K {
+        assert!(hash < self.size());
+        (hash / self.n(), hash % self.n())
+    }
+}
+
+impl OrderedHash for Sudoku {}
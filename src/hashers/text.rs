@@ -0,0 +1,101 @@
+// phf_mut – Perfectly hashed mutable containers
+// Copyright (C) 2017  Ben Wiederhake
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+
+use std::ops::RangeInclusive;
+use {PerfectHash, HashInverse, OrderedHash};
+
+/// First and last code point of the surrogate range,
+/// which contains no valid `char`s.
+const SURROGATES: (u32, u32) = (0xD800, 0xDFFF);
+const SURROGATE_COUNT: u32 = SURROGATES.1 - SURROGATES.0 + 1;
+
+/// A contiguous range of `char`s, e.g. `'a'..='z'` or all of ASCII.
+/// If the range spans the surrogate code points (which are not valid
+/// `char`s), they are skipped, so the hash stays minimal.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct CharRange {
+    first: char,
+    last: char,
+}
+
+impl CharRange {
+    /// Create a new hasher for a non-empty, inclusive range of `char`s.
+    pub fn new(range: RangeInclusive<char>) -> Self {
+        let (first, last) = range.into_inner();
+        assert!(first <= last, "Empty range {:?}..={:?}", first, last);
+        CharRange { first, last }
+    }
+
+    /// Create a new hasher for all 128 ASCII characters.
+    pub fn ascii() -> Self {
+        CharRange::new('\0'..='\x7F')
+    }
+
+    /// The first `char` of the range.
+    pub fn first(&self) -> char {
+        self.first
+    }
+
+    /// The last `char` of the range (inclusive).
+    pub fn last(&self) -> char {
+        self.last
+    }
+
+    /// Returns whether the range contains the given `char`.
+    pub fn contains(&self, c: char) -> bool {
+        self.first <= c && c <= self.last
+    }
+
+    /// Number of skipped surrogates below the code point `c`.
+    fn gap_below(&self, c: u32) -> u32 {
+        if (self.first as u32) < SURROGATES.0 && c > SURROGATES.1 {
+            SURROGATE_COUNT
+        } else {
+            0
+        }
+    }
+}
+
+impl PerfectHash for CharRange {
+    type K = char;
+
+    fn hash(&self, k: Self::K) -> usize {
+        assert!(self.contains(k), "{:?} is not in {:?}", k, self);
+        (k as u32 - self.first as u32 - self.gap_below(k as u32)) as usize
+    }
+
+    fn hash_ref(&self, k: &Self::K) -> usize {
+        self.hash(*k)
+    }
+
+    fn size(&self) -> usize {
+        self.hash(self.last) + 1
+    }
+}
+
+impl HashInverse for CharRange {
+    fn invert(&self, hash: usize) -> Self::K {
+        assert!(hash < self.size());
+        let mut c = self.first as u32 + hash as u32;
+        if (self.first as u32) < SURROGATES.0 && c >= SURROGATES.0 {
+            c += SURROGATE_COUNT;
+        }
+        std::char::from_u32(c).expect("Skipped all surrogates")
+    }
+}
+
+impl OrderedHash for CharRange {}
@@ -16,10 +16,26 @@
 
 //! Perfectly hashed mutable containers.
 
-extern crate bit_vec;
+#![cfg_attr(feature = "allocator_api", feature(allocator_api))]
 
+#[cfg(feature = "chrono")]
+extern crate chrono;
+#[cfg(feature = "enum-map")]
+extern crate enum_map;
+#[cfg(feature = "strum")]
+extern crate strum;
+
+#[cfg(feature = "allocator_api")]
+use std::alloc::Allocator;
+use std::collections::HashMap;
 use std::fmt;
-use std::ops::{Index, IndexMut};
+use std::iter::{FromIterator, FusedIterator};
+use std::marker::PhantomData;
+use std::ops::{Add, AddAssign, BitAnd, BitAndAssign, BitOr, BitOrAssign,
+               BitXor, BitXorAssign, Bound, Index, IndexMut, MulAssign, Not,
+               Range, RangeBounds, Sub, SubAssign};
+
+pub mod hashers;
 
 #[cfg(test)]
 mod tests;
@@ -29,6 +45,29 @@ pub trait PerfectHash {
     type K;
     fn hash(&self, k: Self::K) -> usize;
     fn size(&self) -> usize;
+
+    /// Hash a key by reference, e.g. for lookups with keys that are
+    /// expensive to clone.  The default implementation clones the key,
+    /// so hashers with such keys should override this.
+    fn hash_ref(&self, k: &Self::K) -> usize where Self::K: Clone {
+        self.hash(k.clone())
+    }
+
+    /// Returns whether some key hashes to `hash`.
+    /// Perfect hash functions that are not minimal have "holes",
+    /// i.e., indices below `size()` that no key maps to.
+    /// Those are skipped by iteration and `Debug` output.
+    fn is_valid(&self, hash: usize) -> bool {
+        hash < self.size()
+    }
+
+    /// Returns whether the hasher has no holes, i.e., `is_valid` holds
+    /// for all indices below `size()`.  This allows iteration to skip
+    /// the `is_valid` checks.  Hashers that override `is_valid` to
+    /// introduce holes must override this to return false.
+    fn is_minimal(&self) -> bool {
+        true
+    }
 }
 
 /// Inverse operation of the perfect hash function.
@@ -38,11 +77,67 @@ pub trait HashInverse: PerfectHash {
     fn invert(&self, hash: usize) -> Self::K;
 
     /// Create a new iterator over the hash domain.
-    fn iter(&self) -> KeyIter<Self> {
+    fn iter(&self) -> KeyIter<'_, Self> where Self: Sized {
         KeyIter { next: 0, hash: self }
     }
 }
 
+/// Hashers that can also hash some borrowed or otherwise equivalent form
+/// `Q` of their keys, e.g. `str` for `String` keys, or `[usize]` for
+/// `Vec<usize>` keys.  Hashing `q` must yield the same result as hashing
+/// the key that `q` is equivalent to.
+/// This enables lookups like `Map::get_by` without constructing a key.
+pub trait HashEquivalent<Q: ?Sized>: PerfectHash {
+    fn hash_equivalent(&self, q: &Q) -> usize;
+}
+
+/// Hashers whose domain size is known at compile time.
+/// `SIZE` must always equal `size()`.  This allows the domain size
+/// to be used in constant expressions, e.g. as the length of an array.
+pub trait StaticPerfectHash: PerfectHash {
+    const SIZE: usize;
+}
+
+/// Marker for hashers whose hash order is the natural order of the keys,
+/// i.e., `a < b` if and only if `hash(a) < hash(b)`.
+/// This allows key-range queries like `Map::range` and `Set::range`
+/// to be answered with a contiguous range of indices.
+pub trait OrderedHash: PerfectHash {}
+
+/// The contiguous range of indices whose keys lie within `range`.
+fn hash_range<H, R>(hash: &H, range: R) -> Range<usize>
+    where H: OrderedHash, H::K: Clone, R: RangeBounds<H::K>
+{
+    let start = match range.start_bound() {
+        Bound::Included(k) => hash.hash(k.clone()),
+        Bound::Excluded(k) => hash.hash(k.clone()) + 1,
+        Bound::Unbounded => 0,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(k) => hash.hash(k.clone()) + 1,
+        Bound::Excluded(k) => hash.hash(k.clone()),
+        Bound::Unbounded => hash.size(),
+    };
+    assert!(start <= end, "Range starts after it ends");
+    start..end
+}
+
+/// Translate a range of hash values into a `Range`, clamped to `size`.
+fn index_range<R: RangeBounds<usize>>(range: R, size: usize) -> Range<usize> {
+    let start = match range.start_bound() {
+        Bound::Included(&i) => i,
+        Bound::Excluded(&i) => i + 1,
+        Bound::Unbounded => 0,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(&i) => i + 1,
+        Bound::Excluded(&i) => i,
+        Bound::Unbounded => size,
+    };
+    assert!(start <= end, "Range starts after it ends");
+    start.min(size)..end.min(size)
+}
+
 /// Iterator over the domain of a `PerfectHash`.
 pub struct KeyIter<'a, H: ?Sized + 'a> {
     hash: &'a H,
@@ -54,6 +149,9 @@ impl<'a, H: HashInverse> Iterator for KeyIter<'a, H> {
 
     fn next(&mut self) -> Option<Self::Item> {
         let size = self.hash.size();
+        while self.next < size && !self.hash.is_valid(self.next) {
+            self.next += 1;
+        }
         if self.next == size {
             self.next = 0;
             None
@@ -65,326 +163,3085 @@ impl<'a, H: HashInverse> Iterator for KeyIter<'a, H> {
     }
 }
 
-/// A mutable, perfectly-hashed map.  Note that a `Map` is always full,
-/// so you might prefer `std::collections::HashMap` for sparse maps.
-pub struct Map<V, H> {
-    hash: H,
-    backing: Box<[V]>,
-}
+/// Forward the hasher traits through references and smart pointers,
+/// so that several containers can share a single hasher.
+macro_rules! forward_hash {
+    ($($ptr:ty),*) => {$(
+        impl<'a, H: PerfectHash + ?Sized> PerfectHash for $ptr {
+            type K = H::K;
 
-impl<V: Default, H: PerfectHash> Map<V, H> {
-    /// Create a new `Map` full default values.
-    /// Also see `from_initial` and `from_element`.
-    pub fn new(hash: H) -> Self {
-        let size = hash.size();
-        let mut vec: Vec<V> = Vec::with_capacity(size);
-        for _ in 0..size {
-            vec.push(V::default());
+            fn hash(&self, k: Self::K) -> usize {
+                (**self).hash(k)
+            }
+
+            fn size(&self) -> usize {
+                (**self).size()
+            }
+
+            fn hash_ref(&self, k: &Self::K) -> usize where Self::K: Clone {
+                (**self).hash_ref(k)
+            }
+
+            fn is_valid(&self, hash: usize) -> bool {
+                (**self).is_valid(hash)
+            }
+
+            fn is_minimal(&self) -> bool {
+                (**self).is_minimal()
+            }
         }
-        Map {
-            hash: hash,
-            backing: vec.into_boxed_slice(),
+
+        impl<'a, H: HashInverse + ?Sized> HashInverse for $ptr {
+            fn invert(&self, hash: usize) -> Self::K {
+                (**self).invert(hash)
+            }
         }
-    }
-}
 
-impl<V: Clone, H: PerfectHash> Map<V, H> {
-    /// Create a new `Map` full of copies of some value.
-    /// Also see `from_initial` and `new`.
-    pub fn from_element(hash: H, value: &V) -> Self {
-        let size = hash.size();
-        let mut vec: Vec<V> = Vec::with_capacity(size);
-        for _ in 0..size {
-            vec.push(value.clone());
+        impl<'a, Q: ?Sized, H> HashEquivalent<Q> for $ptr
+            where H: HashEquivalent<Q> + ?Sized
+        {
+            fn hash_equivalent(&self, q: &Q) -> usize {
+                (**self).hash_equivalent(q)
+            }
         }
-        Map {
-            hash: hash,
-            backing: vec.into_boxed_slice(),
+
+        impl<'a, H: OrderedHash + ?Sized> OrderedHash for $ptr {}
+
+        impl<'a, H: StaticPerfectHash + ?Sized> StaticPerfectHash for $ptr {
+            const SIZE: usize = H::SIZE;
         }
-    }
+    )*}
 }
 
-impl<V, H: HashInverse> Map<V, H> {
-    /// Directly create a new iterator over entries:
-    /// `Iterator<Item=(K,&V)>`.
-    pub fn iter(&self) -> MapIter<H, V> {
-        MapIter {
-            backing: self.backing.iter(),
-            hash: &self.hash,
-            pos: 0,
-        }
+forward_hash!(&'a H, Box<H>, std::rc::Rc<H>, std::sync::Arc<H>);
+
+/// A boxed hasher whose concrete type is only known at runtime,
+/// e.g. when the board layout is read from a config file.
+/// Thanks to the forwarding impls, it can be used like any other hasher.
+pub type DynHash<K> = Box<dyn HashInverse<K = K>>;
+
+/// The product of two hashers, keyed by pairs of keys.
+/// The first hasher is the "major" one, i.e., keys that share
+/// the same first component are stored next to each other.
+impl<H1: PerfectHash, H2: PerfectHash> PerfectHash for (H1, H2) {
+    type K = (H1::K, H2::K);
+
+    fn hash(&self, (k1, k2): Self::K) -> usize {
+        self.0.hash(k1) * self.1.size() + self.1.hash(k2)
     }
 
-    /// Directly create a new iterator over mutable entries:
-    /// `Iterator<Item=(K,&mut V)>`.
-    pub fn iter_mut(&mut self) -> MapIterMut<H, V> {
-        MapIterMut {
-            backing: self.backing.iter_mut(),
-            hash: &self.hash,
-            pos: 0,
-        }
+    fn size(&self) -> usize {
+        self.0.size() * self.1.size()
     }
-}
 
-impl<'a, V, H: HashInverse> IntoIterator for &'a Map<V, H> {
-    type Item = (H::K, &'a V);
-    type IntoIter = MapIter<'a, H, V>;
+    fn is_valid(&self, hash: usize) -> bool {
+        let size2 = self.1.size();
+        hash < self.size()
+            && self.0.is_valid(hash / size2) && self.1.is_valid(hash % size2)
+    }
 
-    fn into_iter(self) -> Self::IntoIter {
-        self.iter()
+    fn is_minimal(&self) -> bool {
+        self.0.is_minimal() && self.1.is_minimal()
     }
 }
 
-impl<'a, V, H: HashInverse> IntoIterator for &'a mut Map<V, H> {
-    type Item = (H::K, &'a mut V);
-    type IntoIter = MapIterMut<'a, H, V>;
-
-    fn into_iter(self) -> Self::IntoIter {
-        self.iter_mut()
+impl<H1: HashInverse, H2: HashInverse> HashInverse for (H1, H2) {
+    fn invert(&self, hash: usize) -> Self::K {
+        let size2 = self.1.size();
+        (self.0.invert(hash / size2), self.1.invert(hash % size2))
     }
 }
 
-pub struct MapIter<'a, H: 'a, V: 'a> {
-    // TODO: Sub-optimal approach.  Now the position is saved twice.
-    backing: std::slice::Iter<'a, V>,
-    hash: &'a H,
-    pos: usize,
+/// Keys are compared lexicographically, just like the hashes.
+impl<H1: OrderedHash, H2: OrderedHash> OrderedHash for (H1, H2) {}
+
+impl<H1, H2> StaticPerfectHash for (H1, H2)
+    where H1: StaticPerfectHash, H2: StaticPerfectHash
+{
+    const SIZE: usize = H1::SIZE * H2::SIZE;
 }
 
-impl<'a, H: HashInverse, V: 'a> Iterator for MapIter<'a, H, V> {
-    type Item = (H::K, &'a V);
+/// The product of three hashers, keyed by triples of keys.
+/// As with pairs, the first hasher is the "major" one.
+impl<H1, H2, H3> PerfectHash for (H1, H2, H3)
+    where H1: PerfectHash, H2: PerfectHash, H3: PerfectHash
+{
+    type K = (H1::K, H2::K, H3::K);
 
-    fn next(&mut self) -> Option<Self::Item> {
-        self.backing.next().map(|value| {
-            let key = self.hash.invert(self.pos);
-            self.pos += 1;
-            (key, value)
-        })
+    fn hash(&self, (k1, k2, k3): Self::K) -> usize {
+        (self.0.hash(k1) * self.1.size() + self.1.hash(k2)) * self.2.size()
+            + self.2.hash(k3)
+    }
+
+    fn size(&self) -> usize {
+        self.0.size() * self.1.size() * self.2.size()
+    }
+
+    fn is_valid(&self, hash: usize) -> bool {
+        if hash >= self.size() {
+            return false;
+        }
+        let size2 = self.1.size();
+        let size3 = self.2.size();
+        let rest = hash / size3;
+        self.0.is_valid(rest / size2)
+            && self.1.is_valid(rest % size2) && self.2.is_valid(hash % size3)
+    }
+
+    fn is_minimal(&self) -> bool {
+        self.0.is_minimal() && self.1.is_minimal() && self.2.is_minimal()
     }
 }
 
-pub struct MapIterMut<'a, H: 'a, V: 'a> {
-    // TODO: Sub-optimal approach.  Now the position is saved twice.
-    backing: std::slice::IterMut<'a, V>,
-    hash: &'a H,
-    pos: usize,
+impl<H1, H2, H3> HashInverse for (H1, H2, H3)
+    where H1: HashInverse, H2: HashInverse, H3: HashInverse
+{
+    fn invert(&self, hash: usize) -> Self::K {
+        let size2 = self.1.size();
+        let size3 = self.2.size();
+        let rest = hash / size3;
+        (self.0.invert(rest / size2),
+         self.1.invert(rest % size2),
+         self.2.invert(hash % size3))
+    }
 }
 
-impl<'a, H: HashInverse, V: 'a> Iterator for MapIterMut<'a, H, V> {
-    type Item = (H::K, &'a mut V);
+impl<H1, H2, H3> OrderedHash for (H1, H2, H3)
+    where H1: OrderedHash, H2: OrderedHash, H3: OrderedHash
+{}
 
-    fn next(&mut self) -> Option<Self::Item> {
-        self.backing.next().map(|value| {
-            let key = self.hash.invert(self.pos);
-            self.pos += 1;
-            (key, value)
-        })
+impl<H1, H2, H3> StaticPerfectHash for (H1, H2, H3)
+    where H1: StaticPerfectHash, H2: StaticPerfectHash, H3: StaticPerfectHash
+{
+    const SIZE: usize = H1::SIZE * H2::SIZE * H3::SIZE;
+}
+
+/// Error when building a container from values whose amount does not
+/// match the hasher's `size()`, see `Map::try_from_initial`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct WrongLength {
+    pub expected: usize,
+    pub actual: usize,
+}
+
+impl fmt::Display for WrongLength {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "expected {} values, got {}", self.expected, self.actual)
     }
 }
 
-impl<V, H: PerfectHash> Map<V, H> {
-    /// Create a new `Map` from a given vector of values.
-    /// The vector must be compatible to the PerfectHash.
-    /// Also see `new` and `from_element`.
-    pub fn from_initial(hash: H, init: Vec<V>) -> Self {
-        let size = hash.size();
-        assert_eq!(size, init.len());
-        Map {
-            hash: hash,
-            backing: init.into_boxed_slice(),
+impl std::error::Error for WrongLength {}
+
+/// Error when reassembling a `Set` from blocks that do not fit the hasher,
+/// see `Set::from_parts`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum InvalidBlocks {
+    /// The amount of blocks does not match the hasher's `size()`.
+    WrongLength(WrongLength),
+    /// The bit of this index is set, but it is not a valid hash value,
+    /// i.e. a hole or past the end.
+    StrayBit(usize),
+}
+
+impl fmt::Display for InvalidBlocks {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            InvalidBlocks::WrongLength(ref err) => err.fmt(fmt),
+            InvalidBlocks::StrayBit(index) => {
+                write!(fmt, "bit {} is set, but not a valid hash value", index)
+            }
         }
     }
+}
 
-    /// Overwrite the currently stored value for key `k` by `v`.
-    /// The name `insert` is s homage to `HashMap::insert`.
-    pub fn insert(&mut self, k: H::K, v: V) {
-        self.backing[self.hash.hash(k)] = v;
-    }
+impl std::error::Error for InvalidBlocks {}
 
-    /// Swaps the currently stored value for key `k` with `v`,
-    /// so the old value is now stored in `v`.
-    pub fn swap(&mut self, k: H::K, v: &mut V) {
-        std::mem::swap(&mut self.backing[self.hash.hash(k)], v);
-    }
+/// Value types whose default value consists of zero bytes only,
+/// see `Map::new_zeroed`.
+///
+/// # Safety
+///
+/// The all-zero bit pattern must be a valid value of the type,
+/// and equal to `Self::default()`.
+pub unsafe trait Zeroable: Default {}
 
-    /// Directly get a reference the value for key `k`.
-    /// Also see the `Index` implementation.
-    pub fn get(&self, k: H::K) -> &V {
-        &self.backing[self.hash.hash(k)]
+macro_rules! impl_zeroable {
+    ($($t:ty)*) => { $(
+        unsafe impl Zeroable for $t {}
+    )* };
+}
+
+impl_zeroable!(u8 u16 u32 u64 u128 usize i8 i16 i32 i64 i128 isize
+               f32 f64 bool char);
+
+/// Contiguous backing store of a `Map`, holding one value per slot.
+/// Implement this to choose a memory strategy for the values,
+/// e.g. a memory-mapped file; see `Map::from_storage`.
+pub trait Storage<V> {
+    fn as_slice(&self) -> &[V];
+}
+
+/// Backing store of a `Map` whose values can be modified.
+pub trait StorageMut<V>: Storage<V> {
+    fn as_mut_slice(&mut self) -> &mut [V];
+}
+
+#[cfg(not(feature = "allocator_api"))]
+impl<V> Storage<V> for Box<[V]> {
+    fn as_slice(&self) -> &[V] {
+        self
     }
+}
 
-    /// Directly get a mutable reference the value for key `k`.
-    /// Also see the `Index` implementation.
-    pub fn get_mut(&mut self, k: H::K) -> &mut V {
-        &mut self.backing[self.hash.hash(k)]
+#[cfg(not(feature = "allocator_api"))]
+impl<V> StorageMut<V> for Box<[V]> {
+    fn as_mut_slice(&mut self) -> &mut [V] {
+        self
     }
 }
 
-impl<V, H> Map<V, H> {
-    /// Returns true if the map contains no elements.
-    pub fn is_empty(&self) -> bool {
-        self.backing.is_empty()
+#[cfg(feature = "allocator_api")]
+impl<V, A: Allocator> Storage<V> for Box<[V], A> {
+    fn as_slice(&self) -> &[V] {
+        self
     }
+}
 
-    /// Returns the amount of entries,
-    /// which is always equal to the hasher's domain (i.e., `hasher.size()`).
-    pub fn len(&self) -> usize {
-        self.backing.len()
+#[cfg(feature = "allocator_api")]
+impl<V, A: Allocator> StorageMut<V> for Box<[V], A> {
+    fn as_mut_slice(&mut self) -> &mut [V] {
+        self
     }
+}
 
-    /// Directly create a new iterator over the values:
-    /// `Iterator<Item=&V>`.
-    pub fn values(&self) -> std::slice::Iter<V> {
-        self.backing.iter()
+impl<V> Storage<V> for Vec<V> {
+    fn as_slice(&self) -> &[V] {
+        self
     }
+}
 
-    /// Directly create a new iterator over the mutable values:
-    /// `Iterator<Item=&mut V>`.
-    pub fn values_mut(&mut self) -> std::slice::IterMut<V> {
-        self.backing.iter_mut()
+impl<V> StorageMut<V> for Vec<V> {
+    fn as_mut_slice(&mut self) -> &mut [V] {
+        self
     }
 }
 
-impl<V, H> fmt::Debug for Map<V, H>
-    where V: fmt::Debug
-{
-    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
-        write!(fmt, "{:?}", &*self.backing)
+impl<V, const N: usize> Storage<V> for [V; N] {
+    fn as_slice(&self) -> &[V] {
+        self
     }
 }
 
-impl<V: Clone, H: Clone> Clone for Map<V, H> {
-    fn clone(&self) -> Self { Self {
-        hash: self.hash.clone(),
-        backing: self.backing.clone(),
-    }}
+impl<V, const N: usize> StorageMut<V> for [V; N] {
+    fn as_mut_slice(&mut self) -> &mut [V] {
+        self
+    }
 }
 
-impl<V, H: PerfectHash> Index<H::K> for Map<V, H> {
-    type Output = V;
+impl<V> Storage<V> for &[V] {
+    fn as_slice(&self) -> &[V] {
+        self
+    }
+}
 
-    fn index(&self, k: H::K) -> &V {
-        self.get(k)
+impl<V> Storage<V> for &mut [V] {
+    fn as_slice(&self) -> &[V] {
+        self
     }
 }
 
-impl<V, H: PerfectHash> IndexMut<H::K> for Map<V, H> {
-    fn index_mut(&mut self, k: H::K) -> &mut V {
-        self.get_mut(k)
+impl<V> StorageMut<V> for &mut [V] {
+    fn as_mut_slice(&mut self) -> &mut [V] {
+        self
     }
 }
 
-/// A mutable, perfectly-hashed set.  Note that a small domain is recommended.
-/// For sparse sets, you might prefer `std::collections::HashSet`.
-pub struct Set<H> {
+/// A mutable, perfectly-hashed map.  Note that a `Map` is always full,
+/// so you might prefer `std::collections::HashMap` for sparse maps.
+/// The values live in a `Storage`, which is a boxed slice by default.
+pub struct Map<V, H, S = Box<[V]>> {
     hash: H,
-    backing: bit_vec::BitVec,
+    backing: S,
+    values: PhantomData<V>,
 }
 
-impl<H: PerfectHash> Set<H> {
-    /// Create a new, empty set.
+/// A read-only `Map` over externally owned values,
+/// e.g. a memory-mapped buffer.
+pub type MapView<'a, V, H> = Map<V, H, &'a [V]>;
+
+/// A `Map` over externally owned, mutable values,
+/// e.g. a memory-mapped buffer.
+pub type MapViewMut<'a, V, H> = Map<V, H, &'a mut [V]>;
+
+/// A `Map` that stores its values inline, without a heap allocation.
+/// `N` must be equal to the hasher's `size()`,
+/// for example `StaticPerfectHash::SIZE`.
+pub type ArrayMap<V, H, const N: usize> = Map<V, H, [V; N]>;
+
+/// A `Map` whose values live for the rest of the program,
+/// see `Map::leak_map`.
+pub type LeakedMap<V, H> = MapViewMut<'static, V, H>;
+
+impl<V: Default, H: PerfectHash> Map<V, H> {
+    /// Create a new `Map` full default values.
+    /// Also see `from_initial`, `from_element`, and `from_fn`,
+    /// the latter of which does not need `V: Default`.
+    /// For large maps of primitive values, `new_zeroed` is faster.
     pub fn new(hash: H) -> Self {
         let size = hash.size();
-        Set {
-            hash: hash,
-            backing: bit_vec::BitVec::from_elem(size, false),
+        Map {
+            hash,
+            backing: std::iter::repeat_with(V::default).take(size).collect(),
+            values: PhantomData,
         }
     }
 
-    /// Insert a key into the set, so that `contains`
-    /// for an equal key returns `true` in the future.
-    /// Returns whether this key already was in the set.
-    pub fn insert(&mut self, k: H::K) -> bool {
-        let idx = self.hash.hash(k);
-        let ret = self.backing.get(idx).unwrap();
-        self.backing.set(idx, true);
-        ret
+    /// Create a new `Map` from key-value pairs.  Keys that do not
+    /// appear hold `V::default()`; if a key appears several times,
+    /// the last value wins.
+    pub fn from_pairs<I>(hash: H, iter: I) -> Self
+        where I: IntoIterator<Item = (H::K, V)>
+    {
+        let mut map = Self::new(hash);
+        map.extend(iter);
+        map
     }
+}
 
-    /// Erases a key from the set, so that `contains`
-    /// for an equal key returns `false` in the future.
-    /// Returns whether this key already was in the set.
-    pub fn erase(&mut self, k: H::K) -> bool {
-        let idx = self.hash.hash(k);
-        let ret = self.backing.get(idx).unwrap();
-        self.backing.set(idx, false);
-        ret
+impl<V: Zeroable, H: PerfectHash> Map<V, H> {
+    /// Create a new `Map` full of default values, like `new`, but by
+    /// allocating zeroed memory instead of writing every slot.
+    pub fn new_zeroed(hash: H) -> Self {
+        let size = hash.size();
+        // SAFETY: All-zero bytes are the default value of any `Zeroable`.
+        let backing = unsafe { Box::<[V]>::new_zeroed_slice(size).assume_init() };
+        Map { hash, backing, values: PhantomData }
     }
+}
 
-    fn has(&self, index: usize) -> bool {
-        self.backing.get(index).unwrap()
+#[cfg(feature = "allocator_api")]
+impl<V: Default, H: PerfectHash, A: Allocator> Map<V, H, Box<[V], A>> {
+    /// Create a new `Map` full of default values, like `new`, but with
+    /// the values allocated by `alloc`, e.g. in an arena or in huge pages.
+    /// Needs the `allocator_api` feature and a nightly compiler.
+    pub fn new_in(hash: H, alloc: A) -> Self {
+        let size = hash.size();
+        let mut values = Vec::with_capacity_in(size, alloc);
+        values.extend(std::iter::repeat_with(V::default).take(size));
+        Map::from_storage(hash, values.into_boxed_slice())
     }
+}
 
-    /// Returns whether the key is in the set.
-    pub fn contains(&self, k: H::K) -> bool {
-        let idx = self.hash.hash(k);
-        self.has(idx)
+impl<V: Default, H: PerfectHash, const N: usize> ArrayMap<V, H, N> {
+    /// Create a new `ArrayMap` full default values.
+    pub fn new_inline(hash: H) -> Self {
+        Map::from_storage(hash, std::array::from_fn(|_| V::default()))
     }
+}
 
-    pub fn is_empty(&self) -> bool {
-        !self.backing.any()
+impl<V, H: StaticPerfectHash, const N: usize> ArrayMap<V, H, N> {
+    /// Create a new `ArrayMap` from values in hash order, like
+    /// `from_storage`.  As this is a `const fn`, it can initialize
+    /// a `static`, see `static_map!`.  Panics, or fails to compile in
+    /// a `const` context, if `N` is not the size of the hasher.
+    pub const fn from_array(hash: H, values: [V; N]) -> Self {
+        assert!(H::SIZE == N, "the array length must be the hasher's SIZE");
+        Map {
+            hash,
+            backing: values,
+            values: PhantomData,
+        }
     }
+}
 
-    pub fn is_full(&self) -> bool {
-        self.backing.all()
+impl<V: Default, H: PerfectHash, S: StorageMut<V>> Map<V, H, S> {
+    /// Move the value for key `k` out of the map,
+    /// leaving `V::default()` behind.
+    pub fn take(&mut self, k: H::K) -> V {
+        std::mem::take(self.get_mut(k))
     }
-}
 
-impl<H: HashInverse> Set<H> {
-    /// Create an iterator over the contained keys.
-    pub fn iter(&self) -> SetIter<H> {
-        SetIter {
-            next: self.backing.len(),
-            set: self,
+    /// Overwrite every value by `V::default()`, reusing the allocation.
+    pub fn reset(&mut self) {
+        for v in self.backing.as_mut_slice() {
+            *v = V::default();
         }
     }
 }
 
-impl<'a, H: HashInverse> IntoIterator for &'a Set<H> {
-    type Item = H::K;
-    type IntoIter = SetIter<'a, H>;
-
-    fn into_iter(self) -> SetIter<'a, H> {
-        self.iter()
+impl<V: Clone, H: PerfectHash> Map<V, H> {
+    /// Create a new `Map` full of copies of some value.
+    /// Also see `from_initial` and `new`.
+    /// Zero values of primitive types are allocated as zeroed memory,
+    /// which is much faster than writing each slot for large maps.
+    pub fn from_element(hash: H, value: &V) -> Self {
+        let size = hash.size();
+        Map {
+            hash,
+            backing: vec![value.clone(); size].into_boxed_slice(),
+            values: PhantomData,
+        }
     }
 }
 
-impl<H: PerfectHash + Default> Default for Set<H> {
-    fn default() -> Self {
-        Self::new(H::default())
+impl<V: Clone, H: PerfectHash, S: StorageMut<V>> Map<V, H, S> {
+    /// Overwrite every value by a copy of `value`, reusing the allocation.
+    pub fn fill(&mut self, value: V) {
+        self.backing.as_mut_slice().fill(value);
     }
 }
 
-impl<H: Clone> Clone for Set<H> {
-    fn clone(&self) -> Self { Self {
-        hash: self.hash.clone(),
-        backing: self.backing.clone(),
-    }}
+impl<V, H: HashInverse> Map<V, H> {
+    /// Create a new `Map` by calling `f` for every key in the domain.
+    /// Panics if the hasher has holes, as there would be no key
+    /// to create their values from; see `new` or `from_pairs` for those.
+    pub fn from_fn<F: FnMut(H::K) -> V>(hash: H, mut f: F) -> Self {
+        let values = (0..hash.size())
+            .map(|i| {
+                assert!(hash.is_valid(i), "from_fn cannot fill hole {}", i);
+                f(hash.invert(i))
+            })
+            .collect::<Vec<_>>();
+        Map::from_initial(hash, values)
+    }
+
+    /// Create a new `Map` over the same domain by transforming
+    /// every entry with `f`.  Holes, if any, have no key to pass to `f`,
+    /// so their values are transformed with `hole` instead.
+    pub fn map_values<U, F, G>(self, mut f: F, mut hole: G) -> Map<U, H>
+        where F: FnMut(H::K, V) -> U, G: FnMut(V) -> U
+    {
+        let Map { hash, backing, .. } = self;
+        let mapped = backing.into_vec().into_iter().enumerate()
+            .map(|(i, v)| if hash.is_valid(i) {
+                f(hash.invert(i), v)
+            } else {
+                hole(v)
+            })
+            .collect::<Vec<_>>();
+        Map::from_initial(hash, mapped)
+    }
 }
 
-pub struct SetIter<'a, H: PerfectHash + 'a> {
-    next: usize,
-    set: &'a Set<H>,
+impl<V: Clone, H: HashInverse> Map<V, H> {
+    /// Move every value into a larger domain of the same kind that embeds
+    /// this one, e.g. a bigger `Grid2`.  New slots hold copies of `fill`.
+    pub fn grow_into(self, bigger: H, fill: V) -> Map<V, H> {
+        let mut grown = Map::from_element(bigger, &fill);
+        let Map { hash, backing, .. } = self;
+        for (i, v) in backing.into_vec().into_iter().enumerate() {
+            if hash.is_valid(i) {
+                grown.insert(hash.invert(i), v);
+            }
+        }
+        grown
+    }
 }
 
-impl<'a, H: HashInverse> Iterator for SetIter<'a, H> {
-    type Item = H::K;
+impl<V: Default, H: HashInverse> Map<V, H> {
+    /// Move every value into its slot under a different hasher,
+    /// where `key_map` translates the keys.  Slots of `hash` that no key
+    /// is mapped to hold `V::default()`.  Panics if two keys are mapped
+    /// to the same key.
+    pub fn rehash<H2, F>(self, hash: H2, mut key_map: F) -> Map<V, H2>
+        where H2: PerfectHash, F: FnMut(H::K) -> H2::K
+    {
+        self.move_into(hash, |k| Some(key_map(k)))
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        let size = self.set.hash.size();
-        if self.next == size {
-            /* Fresh start, or wrapped. */
-            self.next = 0;
-        } else {
-            self.next += 1;
+    /// Keep only the values whose keys `key_map` translates into the
+    /// smaller domain of `hash`, e.g. to crop a grid.  Slots of `hash`
+    /// that no key is mapped to hold `V::default()`.  Panics if two keys
+    /// are mapped to the same key.
+    pub fn restrict_into<H2, F>(self, hash: H2, key_map: F) -> Map<V, H2>
+        where H2: PerfectHash, F: FnMut(H::K) -> Option<H2::K>
+    {
+        self.move_into(hash, key_map)
+    }
+
+    fn move_into<H2, F>(self, hash: H2, mut key_map: F) -> Map<V, H2>
+        where H2: PerfectHash, F: FnMut(H::K) -> Option<H2::K>
+    {
+        let mut slots = std::iter::repeat_with(|| None)
+            .take(hash.size())
+            .collect::<Vec<Option<V>>>();
+        let Map { hash: old, backing, .. } = self;
+        for (i, v) in backing.into_vec().into_iter().enumerate() {
+            if !old.is_valid(i) {
+                continue;
+            }
+            if let Some(k) = key_map(old.invert(i)) {
+                let slot = &mut slots[hash.hash(k)];
+                assert!(slot.is_none(), "Two keys were mapped to the same key");
+                *slot = Some(v);
+            }
         }
-        while self.next < size && !self.set.has(self.next) {
-            self.next += 1;
+        let values = slots.into_iter().map(Option::unwrap_or_default).collect();
+        Map::from_initial(hash, values)
+    }
+}
+
+impl<V: Clone, H: PerfectHash> Map<V, H> {
+    /// Replicate every value along a second dimension given by `other`,
+    /// i.e. the value for `(k1, k2)` is a copy of the value for `k1`.
+    /// This is useful to initialize product-keyed tables from per-axis data.
+    pub fn broadcast<H2: PerfectHash>(self, other: H2) -> Map<V, (H, H2)> {
+        let Map { hash, backing, .. } = self;
+        let size2 = other.size();
+        let mut values = Vec::with_capacity(backing.len() * size2);
+        for v in backing.into_vec() {
+            values.extend(std::iter::repeat_n(v, size2));
         }
-        if self.next == size {
-            None
+        Map::from_initial((hash, other), values)
+    }
+}
+
+impl<V, H1: PerfectHash, H2: PerfectHash> Map<V, (H1, H2)> {
+    /// Swap the components of all keys, so that the second hasher becomes
+    /// the "major" one, i.e., entries that share the same second component
+    /// are now stored next to each other.  This is useful when the access
+    /// pattern changes from row-wise to column-wise.
+    pub fn transpose(self) -> Map<V, (H2, H1)> {
+        let Map { hash: (h1, h2), backing, .. } = self;
+        let (size1, size2) = (h1.size(), h2.size());
+        let mut slots = backing.into_vec().into_iter()
+            .map(Some)
+            .collect::<Vec<_>>();
+        let values = (0..slots.len())
+            .map(|i| slots[(i % size1) * size2 + i / size1].take().unwrap())
+            .collect();
+        Map::from_initial((h2, h1), values)
+    }
+}
+
+impl<V, H1, H2, S: Storage<V>> Map<V, (H1, H2), S>
+    where H1: HashInverse, H2: HashInverse
+{
+    /// Create an iterator over the entries of the row of `k1`,
+    /// i.e. all keys with the first component `k1`.
+    /// The iterator yields only the second component of each key.
+    pub fn row(&self, k1: H1::K) -> MapIter<'_, H2, V> {
+        let size2 = self.hash.1.size();
+        let start = self.hash.0.hash(k1) * size2;
+        MapIter {
+            backing: self.backing.as_slice()[start..start + size2].iter(),
+            hash: &self.hash.1,
+            pos: 0,
+        }
+    }
+
+    /// Create an iterator over the entries of the column of `k2`,
+    /// i.e. all keys with the second component `k2`.
+    /// The iterator yields only the first component of each key.
+    pub fn col(&self, k2: H2::K) -> ColIter<'_, H1, V> {
+        let size2 = self.hash.1.size();
+        let j = self.hash.1.hash(k2);
+        ColIter {
+            backing: self.backing.as_slice().iter().skip(j).step_by(size2),
+            hash: &self.hash.0,
+            pos: 0,
+        }
+    }
+
+    /// Fold the values of each row, i.e. of all keys that share the same
+    /// first component, into a single value per first component.
+    /// `f` also receives the second component of each key,
+    /// e.g. to sum over all opponents to get per-player totals.
+    pub fn reduce_rows<U, F>(&self, init: U, mut f: F) -> Map<U, H1>
+        where H1: Clone, U: Clone, F: FnMut(U, H2::K, &V) -> U
+    {
+        let (h1, h2) = &self.hash;
+        let (size1, size2) = (h1.size(), h2.size());
+        let values = (0..size1)
+            .map(|i| (0..size2)
+                .filter(|&j| h1.is_valid(i) && h2.is_valid(j))
+                .fold(init.clone(), |acc, j| {
+                    f(acc, h2.invert(j), &self.backing.as_slice()[i * size2 + j])
+                }))
+            .collect();
+        Map::from_initial(h1.clone(), values)
+    }
+
+    /// Fold the values of each column, i.e. of all keys that share the same
+    /// second component, into a single value per second component.
+    /// `f` also receives the first component of each key.
+    pub fn reduce_cols<U, F>(&self, init: U, mut f: F) -> Map<U, H2>
+        where H2: Clone, U: Clone, F: FnMut(U, H1::K, &V) -> U
+    {
+        let (h1, h2) = &self.hash;
+        let (size1, size2) = (h1.size(), h2.size());
+        let values = (0..size2)
+            .map(|j| (0..size1)
+                .filter(|&i| h1.is_valid(i) && h2.is_valid(j))
+                .fold(init.clone(), |acc, i| {
+                    f(acc, h1.invert(i), &self.backing.as_slice()[i * size2 + j])
+                }))
+            .collect();
+        Map::from_initial(h2.clone(), values)
+    }
+}
+
+impl<V, H1, H2, S: StorageMut<V>> Map<V, (H1, H2), S>
+    where H1: HashInverse, H2: HashInverse
+{
+    /// Like `row`, but with mutable values.
+    pub fn row_mut(&mut self, k1: H1::K) -> MapIterMut<'_, H2, V> {
+        let size2 = self.hash.1.size();
+        let start = self.hash.0.hash(k1) * size2;
+        MapIterMut {
+            backing: self.backing.as_mut_slice()[start..start + size2].iter_mut(),
+            hash: &self.hash.1,
+            pos: 0,
+        }
+    }
+
+    /// Like `col`, but with mutable values.
+    pub fn col_mut(&mut self, k2: H2::K) -> ColIterMut<'_, H1, V> {
+        let size2 = self.hash.1.size();
+        let j = self.hash.1.hash(k2);
+        ColIterMut {
+            backing: self.backing.as_mut_slice().iter_mut().skip(j).step_by(size2),
+            hash: &self.hash.0,
+            pos: 0,
+        }
+    }
+}
+
+impl<V, H: HashInverse, S: Storage<V>> Map<V, H, S> {
+    /// Directly create a new iterator over entries:
+    /// `Iterator<Item=(K,&V)>`.
+    pub fn iter(&self) -> MapIter<'_, H, V> {
+        MapIter {
+            backing: self.backing.as_slice().iter(),
+            hash: &self.hash,
+            pos: 0,
+        }
+    }
+
+    /// A read-only view of the entries whose keys satisfy `pred`,
+    /// e.g. only the boundary cells of a grid.
+    pub fn sub_view<P>(&self, pred: P) -> SubMapView<'_, V, H, P>
+        where P: Fn(&H::K) -> bool
+    {
+        SubMapView {
+            backing: self.backing.as_slice(),
+            hash: &self.hash,
+            pred,
+        }
+    }
+
+    /// Create a new iterator over the entries whose hash values lie
+    /// within `range`, e.g. to split the work between threads.
+    pub fn iter_range<R: RangeBounds<usize>>(&self, range: R) -> MapIter<'_, H, V> {
+        let indices = index_range(range, self.len());
+        MapIter {
+            pos: indices.start,
+            backing: self.backing.as_slice()[indices].iter(),
+            hash: &self.hash,
+        }
+    }
+
+    /// Split the entries into blocks of `size` slots (the last one may be
+    /// shorter), e.g. to process them in cache-sized batches or to hand
+    /// them to worker threads: `Iterator<Item=MapIter>`.
+    pub fn chunks(&self, size: usize) -> Chunks<'_, H, V> {
+        Chunks {
+            backing: self.backing.as_slice().chunks(size),
+            hash: &self.hash,
+            pos: 0,
+        }
+    }
+
+    /// The hash values and values of all entries, holes excluded.
+    fn valid_slots(&self) -> impl Iterator<Item = (usize, &V)> {
+        self.backing.as_slice().iter().enumerate()
+            .filter(move |&(i, _)| self.hash.is_valid(i))
+    }
+
+    /// Create a new iterator over the entries that do not hold
+    /// `V::default()`, e.g. the non-zero counts: `Iterator<Item=(K,&V)>`.
+    pub fn iter_non_default(&self) -> NonDefault<'_, H, V>
+        where V: Default + PartialEq
+    {
+        NonDefault {
+            backing: self.backing.as_slice().iter(),
+            hash: &self.hash,
+            pos: 0,
+            default: V::default(),
+        }
+    }
+
+    /// Build a reverse index from each value to the keys holding it,
+    /// each list in hash order.
+    pub fn invert_values(&self) -> HashMap<V, Vec<H::K>>
+        where V: Eq + std::hash::Hash + Clone
+    {
+        let mut index = HashMap::<V, Vec<H::K>>::new();
+        for (i, v) in self.valid_slots() {
+            index.entry(v.clone()).or_default().push(self.hash.invert(i));
+        }
+        index
+    }
+
+    /// Group the keys by their values, e.g. to turn a labeling into
+    /// per-label key lists: `Iterator<Item=(&V, Iterator<Item=K>)>`.
+    /// Groups appear in the order of their first key, and keys in hash
+    /// order.  As this only needs `V: PartialEq`, values are compared
+    /// against every group; see `invert_values` for many distinct values.
+    pub fn group_by_value(&self) -> GroupByValue<'_, H, V> where V: PartialEq {
+        let mut groups: Vec<(&V, Vec<usize>)> = Vec::new();
+        for (i, v) in self.valid_slots() {
+            match groups.iter_mut().find(|group| group.0 == v) {
+                Some(group) => group.1.push(i),
+                None => groups.push((v, vec![i])),
+            }
+        }
+        GroupByValue {
+            groups: groups.into_iter(),
+            hash: &self.hash,
+        }
+    }
+
+    /// Reduce all entries to a single value, in hash order,
+    /// e.g. a weighted sum over grid coordinates.
+    pub fn fold_with_keys<B, F>(&self, init: B, mut f: F) -> B
+        where F: FnMut(B, H::K, &V) -> B
+    {
+        self.valid_slots()
+            .fold(init, |acc, (i, v)| f(acc, self.hash.invert(i), v))
+    }
+
+    /// The first key in hash order for which `f` returns true.
+    pub fn find_key<F>(&self, mut f: F) -> Option<H::K>
+        where F: FnMut(H::K, &V) -> bool
+    {
+        self.valid_slots()
+            .find(|&(i, v)| f(self.hash.invert(i), v))
+            .map(|(i, _)| self.hash.invert(i))
+    }
+
+    /// The amount of entries for which `f` returns true.
+    pub fn count_where<F>(&self, mut f: F) -> usize
+        where F: FnMut(H::K, &V) -> bool
+    {
+        self.valid_slots()
+            .filter(|&(i, v)| f(self.hash.invert(i), v))
+            .count()
+    }
+
+    /// The entry with the greatest value.  If several values are equally
+    /// great, the last one in hash order is returned, like `Iterator::max`.
+    pub fn max_by_value(&self) -> Option<(H::K, &V)> where V: Ord {
+        self.valid_slots()
+            .max_by(|a, b| a.1.cmp(b.1))
+            .map(|(i, v)| (self.hash.invert(i), v))
+    }
+
+    /// The entry with the least value.  If several values are equally
+    /// small, the first one in hash order is returned, like `Iterator::min`.
+    pub fn min_by_value(&self) -> Option<(H::K, &V)> where V: Ord {
+        self.valid_slots()
+            .min_by(|a, b| a.1.cmp(b.1))
+            .map(|(i, v)| (self.hash.invert(i), v))
+    }
+
+    /// The entry with the greatest projection `f` of its value.
+    pub fn max_by_value_key<B: Ord, F>(&self, mut f: F) -> Option<(H::K, &V)>
+        where F: FnMut(&V) -> B
+    {
+        self.valid_slots()
+            .max_by_key(|&(_, v)| f(v))
+            .map(|(i, v)| (self.hash.invert(i), v))
+    }
+
+    /// The entry with the least projection `f` of its value.
+    pub fn min_by_value_key<B: Ord, F>(&self, mut f: F) -> Option<(H::K, &V)>
+        where F: FnMut(&V) -> B
+    {
+        self.valid_slots()
+            .min_by_key(|&(_, v)| f(v))
+            .map(|(i, v)| (self.hash.invert(i), v))
+    }
+
+    /// Hash values of all entries (holes excluded), sorted stably by value.
+    fn sorted_indices<F>(&self, mut cmp: F) -> Vec<usize>
+        where F: FnMut(&V, &V) -> std::cmp::Ordering
+    {
+        let backing = self.backing.as_slice();
+        let mut indices = self.valid_slots().map(|(i, _)| i).collect::<Vec<_>>();
+        indices.sort_by(|&a, &b| cmp(&backing[a], &backing[b]));
+        indices
+    }
+
+    /// Create a new iterator over the entries in the order of their values,
+    /// as determined by `cmp`: `Iterator<Item=(K,&V)>`.
+    /// Entries with equal values stay in hash order.
+    pub fn iter_sorted_by<F>(&self, cmp: F) -> SortedIter<'_, H, V>
+        where F: FnMut(&V, &V) -> std::cmp::Ordering
+    {
+        SortedIter {
+            order: self.sorted_indices(cmp).into_iter(),
+            backing: self.backing.as_slice(),
+            hash: &self.hash,
+        }
+    }
+
+    /// All keys, ordered by their values as determined by `cmp`.
+    /// Keys with equal values stay in hash order.
+    pub fn argsort_by<F>(&self, cmp: F) -> Vec<H::K>
+        where F: FnMut(&V, &V) -> std::cmp::Ordering
+    {
+        self.sorted_indices(cmp).into_iter().map(|i| self.hash.invert(i)).collect()
+    }
+
+    /// The `k` entries with the greatest values as determined by `cmp`,
+    /// greatest first, e.g. the hottest cells of a counter map.
+    pub fn top_k<F>(&self, k: usize, mut cmp: F) -> Vec<(H::K, &V)>
+        where F: FnMut(&V, &V) -> std::cmp::Ordering
+    {
+        let backing = self.backing.as_slice();
+        let mut indices = self.valid_slots().map(|(i, _)| i).collect::<Vec<_>>();
+        let mut by_value_desc = |a: &usize, b: &usize| cmp(&backing[*b], &backing[*a]);
+        if k < indices.len() {
+            if k > 0 {
+                indices.select_nth_unstable_by(k - 1, &mut by_value_desc);
+            }
+            indices.truncate(k);
+        }
+        indices.sort_by(by_value_desc);
+        indices.into_iter().map(|i| (self.hash.invert(i), &backing[i])).collect()
+    }
+
+    /// Directly create a new iterator over the keys:
+    /// `Iterator<Item=K>`.  This is the hasher's domain.
+    pub fn keys(&self) -> KeyIter<'_, H> {
+        self.hash.iter()
+    }
+
+    /// Create a new iterator over all entries that differ between
+    /// this map and another map over the same domain:
+    /// `Iterator<Item=(K,&V,&V)>`.
+    pub fn diff<'a, S2>(&'a self, other: &'a Map<V, H, S2>) -> Diff<'a, H, V>
+        where V: PartialEq, S2: Storage<V>
+    {
+        assert_eq!(self.len(), other.len());
+        Diff {
+            hash: &self.hash,
+            left: self.backing.as_slice(),
+            right: other.backing.as_slice(),
+            pos: 0,
+        }
+    }
+}
+
+impl<V, H: HashInverse, S: StorageMut<V>> Map<V, H, S> {
+    /// Directly create a new iterator over mutable entries:
+    /// `Iterator<Item=(K,&mut V)>`.
+    pub fn iter_mut(&mut self) -> MapIterMut<'_, H, V> {
+        MapIterMut {
+            backing: self.backing.as_mut_slice().iter_mut(),
+            hash: &self.hash,
+            pos: 0,
+        }
+    }
+
+    /// Split the mutable entries into blocks of `size` slots (the last one
+    /// may be shorter), e.g. to hand them to worker threads:
+    /// `Iterator<Item=MapIterMut>`.
+    pub fn chunks_mut(&mut self, size: usize) -> ChunksMut<'_, H, V> {
+        ChunksMut {
+            backing: self.backing.as_mut_slice().chunks_mut(size),
+            hash: &self.hash,
+            pos: 0,
+        }
+    }
+
+    /// A mutable view of the entries whose keys satisfy `pred`.
+    pub fn sub_view_mut<P>(&mut self, pred: P) -> SubMapViewMut<'_, V, H, P>
+        where P: Fn(&H::K) -> bool
+    {
+        SubMapViewMut {
+            backing: self.backing.as_mut_slice(),
+            hash: &self.hash,
+            pred,
+        }
+    }
+
+    /// Split the entries into `n` disjoint partitions, as chosen by
+    /// `classify` for each key, e.g. to hand different regions to
+    /// different threads.  Panics if `classify` returns `n` or more.
+    pub fn split_partitions<F>(&mut self, n: usize, mut classify: F)
+        -> Vec<Partition<'_, H, V>>
+        where F: FnMut(&H::K) -> usize
+    {
+        let hash = &self.hash;
+        let mut partitions = (0..n)
+            .map(|_| Partition { hash, entries: Vec::new() })
+            .collect::<Vec<_>>();
+        for (i, v) in self.backing.as_mut_slice().iter_mut().enumerate() {
+            if hash.is_valid(i) {
+                let part = classify(&hash.invert(i));
+                assert!(part < n, "Partition {} out of range", part);
+                partitions[part].entries.push((i, v));
+            }
+        }
+        partitions
+    }
+
+    /// Call `f` once for every unordered pair of distinct entries,
+    /// with mutable access to both values, e.g. for N-body interactions.
+    /// The entry with the smaller hash value comes first.
+    pub fn for_each_pair_mut<F>(&mut self, mut f: F)
+        where F: FnMut(H::K, &mut V, H::K, &mut V)
+    {
+        let hash = &self.hash;
+        let backing = self.backing.as_mut_slice();
+        for i in (0..backing.len()).filter(|&i| hash.is_valid(i)) {
+            let (head, tail) = backing.split_at_mut(i + 1);
+            let first = &mut head[i];
+            for (j, second) in tail.iter_mut().enumerate() {
+                let j = i + 1 + j;
+                if hash.is_valid(j) {
+                    f(hash.invert(i), first, hash.invert(j), second);
+                }
+            }
+        }
+    }
+
+    /// Create a cursor at the first entry, which can move back and forth
+    /// in hash order and modify the values along the way.
+    pub fn cursor_mut(&mut self) -> CursorMut<'_, H, V> {
+        let mut cursor = CursorMut {
+            backing: self.backing.as_mut_slice(),
+            hash: &self.hash,
+            pos: 0,
+        };
+        cursor.pos = cursor.next_valid(0);
+        cursor
+    }
+
+    /// Call `f` with every key and its mutable value, in hash order.
+    /// This is the in-place counterpart to `iter_mut`.
+    pub fn apply<F: FnMut(H::K, &mut V)>(&mut self, mut f: F) {
+        let hash = &self.hash;
+        for (i, v) in self.backing.as_mut_slice().iter_mut().enumerate() {
+            if hash.is_valid(i) {
+                f(hash.invert(i), v);
+            }
+        }
+    }
+
+    /// Reset all values for which `f` returns true to `V::default()`.
+    /// This is the analogue of `retain` for an always-full map.
+    pub fn reset_where<F>(&mut self, mut f: F)
+        where V: Default, F: FnMut(H::K, &V) -> bool
+    {
+        for (k, v) in self.iter_mut() {
+            if f(k, v) {
+                *v = V::default();
+            }
+        }
+    }
+}
+
+impl<V, H, S: Storage<V>> Map<V, H, S>
+    where H: OrderedHash + HashInverse, H::K: Clone
+{
+    /// Create a new iterator over the entries whose keys lie within `range`,
+    /// in key order.  Like `BTreeMap::range`, but without any searching.
+    pub fn range<R: RangeBounds<H::K>>(&self, range: R) -> MapIter<'_, H, V> {
+        self.iter_range(hash_range(&self.hash, range))
+    }
+}
+
+impl<V, H, S: StorageMut<V>> Map<V, H, S>
+    where H: OrderedHash + HashInverse, H::K: Clone
+{
+    /// Create a new iterator over the mutable entries whose keys lie
+    /// within `range`, in key order.
+    pub fn range_mut<R>(&mut self, range: R) -> MapIterMut<'_, H, V>
+        where R: RangeBounds<H::K>
+    {
+        let indices = hash_range(&self.hash, range);
+        MapIterMut {
+            pos: indices.start,
+            backing: self.backing.as_mut_slice()[indices].iter_mut(),
+            hash: &self.hash,
+        }
+    }
+}
+
+impl<'a, V, H: HashInverse, S: Storage<V>> IntoIterator for &'a Map<V, H, S> {
+    type Item = (H::K, &'a V);
+    type IntoIter = MapIter<'a, H, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, V, H, S> IntoIterator for &'a mut Map<V, H, S>
+    where H: HashInverse, S: StorageMut<V>
+{
+    type Item = (H::K, &'a mut V);
+    type IntoIter = MapIterMut<'a, H, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl<V, H: HashInverse> IntoIterator for Map<V, H> {
+    type Item = (H::K, V);
+    type IntoIter = IntoIter<H, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter {
+            backing: self.backing.into_vec().into_iter(),
+            hash: self.hash,
+            pos: 0,
+        }
+    }
+}
+
+/// Owning iterator over the entries of a `Map`, skipping holes.
+pub struct IntoIter<H, V> {
+    backing: std::vec::IntoIter<V>,
+    hash: H,
+    pos: usize,
+}
+
+impl<H: HashInverse, V> Iterator for IntoIter<H, V> {
+    type Item = (H::K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for value in &mut self.backing {
+            let pos = self.pos;
+            self.pos += 1;
+            if self.hash.is_valid(pos) {
+                return Some((self.hash.invert(pos), value));
+            }
+        }
+        None
+    }
+}
+
+pub struct MapIter<'a, H: 'a, V: 'a> {
+    // TODO: Sub-optimal approach.  Now the position is saved twice.
+    backing: std::slice::Iter<'a, V>,
+    hash: &'a H,
+    pos: usize,
+}
+
+impl<'a, H, V: 'a> MapIter<'a, H, V> {
+    /// The remaining values as a slice, holes included.
+    pub fn as_slice(&self) -> &'a [V] {
+        self.backing.as_slice()
+    }
+}
+
+impl<'a, H: HashInverse, V: 'a> Iterator for MapIter<'a, H, V> {
+    type Item = (H::K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for value in &mut self.backing {
+            let pos = self.pos;
+            self.pos += 1;
+            if self.hash.is_valid(pos) {
+                return Some((self.hash.invert(pos), value));
+            }
+        }
+        None
+    }
+}
+
+pub struct MapIterMut<'a, H: 'a, V: 'a> {
+    // TODO: Sub-optimal approach.  Now the position is saved twice.
+    backing: std::slice::IterMut<'a, V>,
+    hash: &'a H,
+    pos: usize,
+}
+
+impl<'a, H, V: 'a> MapIterMut<'a, H, V> {
+    /// The remaining values as a slice, holes included.
+    pub fn into_slice(self) -> &'a mut [V] {
+        self.backing.into_slice()
+    }
+}
+
+impl<'a, H: HashInverse, V: 'a> Iterator for MapIterMut<'a, H, V> {
+    type Item = (H::K, &'a mut V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for value in &mut self.backing {
+            let pos = self.pos;
+            self.pos += 1;
+            if self.hash.is_valid(pos) {
+                return Some((self.hash.invert(pos), value));
+            }
+        }
+        None
+    }
+}
+
+impl<V, H: PerfectHash> Map<V, H> {
+    /// Create a new `Map` from a given vector of values.
+    /// The vector must be compatible to the PerfectHash.
+    /// Also see `new` and `from_element`.
+    pub fn from_initial(hash: H, init: Vec<V>) -> Self {
+        Map::from_storage(hash, init.into_boxed_slice())
+    }
+
+    /// Like `from_initial`, but returns an error instead of panicking
+    /// if the vector is not compatible to the PerfectHash.
+    pub fn try_from_initial(hash: H, init: Vec<V>) -> Result<Self, WrongLength> {
+        let size = hash.size();
+        if size != init.len() {
+            return Err(WrongLength { expected: size, actual: init.len() });
+        }
+        Ok(Map::from_initial(hash, init))
+    }
+
+    /// Consume the map, creating an iterator over the values:
+    /// `Iterator<Item=V>`.
+    pub fn into_values(self) -> IntoValues<H, V> {
+        IntoValues {
+            backing: self.backing.into_vec().into_iter(),
+            hash: self.hash,
+            pos: 0,
+        }
+    }
+}
+
+impl<V, H: PerfectHash, S: Storage<V>> Map<V, H, S> {
+    /// Create a new `Map` on top of an existing storage,
+    /// e.g. a `Vec`, an array, or a borrowed slice (see `MapView`).
+    /// The storage must be compatible to the PerfectHash.
+    pub fn from_storage(hash: H, backing: S) -> Self {
+        assert_eq!(hash.size(), backing.as_slice().len());
+        Map {
+            hash,
+            backing,
+            values: PhantomData,
+        }
+    }
+
+    /// Directly get a reference the value for key `k`.
+    /// Also see the `Index` implementation.
+    pub fn get(&self, k: H::K) -> &V {
+        &self.backing.as_slice()[self.hash.hash(k)]
+    }
+
+    /// The hash value of key `k`, i.e. the index of its slot.
+    /// It can be stored and reused with `get_by_hash`.
+    pub fn index_of(&self, k: H::K) -> usize {
+        self.hash.hash(k)
+    }
+
+    /// Directly get a reference to the value in slot `hash`,
+    /// e.g. as returned by `index_of`.
+    pub fn get_by_hash(&self, hash: usize) -> &V {
+        &self.backing.as_slice()[hash]
+    }
+
+    /// Like `get`, but without checking the hash value against the bounds
+    /// of the storage.
+    ///
+    /// # Safety
+    ///
+    /// The hasher must hash `k` to a value below its `size()`, and the
+    /// storage must still hold `size()` values.  Both hold for correct
+    /// `PerfectHash` and `Storage` implementations, but those traits are
+    /// not `unsafe`, so the compiler cannot rely on them.
+    pub unsafe fn get_unchecked(&self, k: H::K) -> &V {
+        let idx = self.hash.hash(k);
+        self.backing.as_slice().get_unchecked(idx)
+    }
+
+    /// Like `get`, but takes the key by reference.
+    /// This avoids cloning keys for hashers that implement `hash_ref`.
+    pub fn get_ref(&self, k: &H::K) -> &V where H::K: Clone {
+        &self.backing.as_slice()[self.hash.hash_ref(k)]
+    }
+
+    /// Like `get`, but takes any form of the key that the hasher
+    /// can hash, e.g. `&str` instead of a `String`.
+    pub fn get_by<Q: ?Sized>(&self, q: &Q) -> &V where H: HashEquivalent<Q> {
+        &self.backing.as_slice()[self.hash.hash_equivalent(q)]
+    }
+
+    /// Look up the values for many keys at once.  All hashes are computed
+    /// before any value is touched, which helps the CPU overlap the
+    /// memory accesses in lookup-heavy workloads.
+    pub fn get_batch(&self, ks: &[H::K]) -> Vec<&V> where H::K: Clone {
+        let hashes = ks.iter().map(|k| self.hash.hash_ref(k)).collect::<Vec<_>>();
+        let backing = self.backing.as_slice();
+        hashes.into_iter().map(|h| &backing[h]).collect()
+    }
+
+    /// Combine this map slot by slot with another map over the same
+    /// domain, creating a new `Map`.  Also see `zip_assign`.
+    pub fn zip_with<U, W, S2, F>(&self, other: &Map<U, H, S2>, mut f: F)
+        -> Map<W, H>
+        where H: Clone, S2: Storage<U>, F: FnMut(&V, &U) -> W
+    {
+        assert_eq!(self.len(), other.len());
+        let zipped = self.backing.as_slice().iter()
+            .zip(other.backing.as_slice())
+            .map(|(v, u)| f(v, u))
+            .collect::<Vec<_>>();
+        Map::from_initial(self.hash.clone(), zipped)
+    }
+
+    /// Directly create a new iterator over the values:
+    /// `Iterator<Item=&V>`.
+    pub fn values(&self) -> Values<'_, H, V> {
+        Values::new(&self.hash, self.backing.as_slice(), 0)
+    }
+
+    /// Create a new iterator over the values whose hash values lie
+    /// within `range`, e.g. to split the work between threads.
+    pub fn values_range<R: RangeBounds<usize>>(&self, range: R) -> Values<'_, H, V> {
+        let indices = index_range(range, self.len());
+        let start = indices.start;
+        Values::new(&self.hash, &self.backing.as_slice()[indices], start)
+    }
+
+    /// The amount of entries that do not hold `V::default()`.
+    pub fn count_non_default(&self) -> usize where V: Default + PartialEq {
+        let default = V::default();
+        self.values().filter(|&v| *v != default).count()
+    }
+
+    /// Format the entries as a map from keys to values.
+    /// `Debug` itself only prints the values, as it has to work for
+    /// hashers without `HashInverse`, too.
+    pub fn debug_entries(&self) -> DebugEntries<'_, V, H, S>
+        where H: HashInverse
+    {
+        DebugEntries { map: self }
+    }
+}
+
+impl<V, H: PerfectHash, S: StorageMut<V>> Map<V, H, S> {
+    /// Overwrite the currently stored value for key `k` by `v`.
+    /// The name `insert` is s homage to `HashMap::insert`.
+    pub fn insert(&mut self, k: H::K, v: V) {
+        *self.get_mut(k) = v;
+    }
+
+    /// Swaps the currently stored value for key `k` with `v`,
+    /// so the old value is now stored in `v`.
+    pub fn swap(&mut self, k: H::K, v: &mut V) {
+        std::mem::swap(self.get_mut(k), v);
+    }
+
+    /// Swap the values stored for keys `k1` and `k2`.
+    pub fn swap_entries(&mut self, k1: H::K, k2: H::K) {
+        let h1 = self.hash.hash(k1);
+        let h2 = self.hash.hash(k2);
+        self.backing.as_mut_slice().swap(h1, h2);
+    }
+
+    /// Overwrite the currently stored value for key `k` by `v`,
+    /// and return the previous value.  Also see `insert`.
+    pub fn replace(&mut self, k: H::K, v: V) -> V {
+        std::mem::replace(self.get_mut(k), v)
+    }
+
+    /// Directly get a mutable reference the value for key `k`.
+    /// Also see the `Index` implementation.
+    pub fn get_mut(&mut self, k: H::K) -> &mut V {
+        &mut self.backing.as_mut_slice()[self.hash.hash(k)]
+    }
+
+    /// Directly get a mutable reference to the value in slot `hash`,
+    /// e.g. as returned by `index_of`.
+    pub fn get_by_hash_mut(&mut self, hash: usize) -> &mut V {
+        &mut self.backing.as_mut_slice()[hash]
+    }
+
+    /// Like `get_mut`, but without checking the hash value against the
+    /// bounds of the storage.
+    ///
+    /// # Safety
+    ///
+    /// See `get_unchecked`.
+    pub unsafe fn get_unchecked_mut(&mut self, k: H::K) -> &mut V {
+        let idx = self.hash.hash(k);
+        self.backing.as_mut_slice().get_unchecked_mut(idx)
+    }
+
+    /// Get mutable references to the values of several keys at once.
+    /// Returns `None` if any two keys refer to the same slot.
+    pub fn get_many_mut<const N: usize>(&mut self, ks: [H::K; N])
+        -> Option<[&mut V; N]>
+    {
+        let hashes = ks.map(|k| self.hash.hash(k));
+        self.backing.as_mut_slice().get_disjoint_mut(hashes).ok()
+    }
+
+    /// Like `get_mut`, but takes the key by reference.
+    /// This avoids cloning keys for hashers that implement `hash_ref`.
+    pub fn get_ref_mut(&mut self, k: &H::K) -> &mut V where H::K: Clone {
+        &mut self.backing.as_mut_slice()[self.hash.hash_ref(k)]
+    }
+
+    /// Like `get_mut`, but takes any form of the key that the hasher
+    /// can hash, e.g. `&str` instead of a `String`.
+    pub fn get_by_mut<Q: ?Sized>(&mut self, q: &Q) -> &mut V
+        where H: HashEquivalent<Q>
+    {
+        &mut self.backing.as_mut_slice()[self.hash.hash_equivalent(q)]
+    }
+
+    /// Call `f` with the value for each of the given keys, computing all
+    /// hashes first; see `get_batch`.  Repeated keys are visited repeatedly.
+    pub fn for_each_batch_mut<F>(&mut self, ks: &[H::K], mut f: F)
+        where H::K: Clone, F: FnMut(&H::K, &mut V)
+    {
+        let hashes = ks.iter().map(|k| self.hash.hash_ref(k)).collect::<Vec<_>>();
+        let backing = self.backing.as_mut_slice();
+        for (k, h) in ks.iter().zip(hashes) {
+            f(k, &mut backing[h]);
+        }
+    }
+
+    /// Modify the value for key `k` in place, hashing `k` only once.
+    pub fn update<F: FnOnce(&mut V)>(&mut self, k: H::K, f: F) {
+        f(self.get_mut(k))
+    }
+
+    /// Get the slot for key `k`, for read-modify-write sequences
+    /// that should hash `k` only once.
+    pub fn entry(&mut self, k: H::K) -> Entry<'_, V> {
+        Entry { value: self.get_mut(k) }
+    }
+
+    /// Combine this map slot by slot with another map over the same
+    /// domain, in place.  Also see `zip_with`.
+    pub fn zip_assign<U, S2, F>(&mut self, other: &Map<U, H, S2>, mut f: F)
+        where S2: Storage<U>, F: FnMut(&mut V, &U)
+    {
+        assert_eq!(self.len(), other.len());
+        let pairs = self.backing.as_mut_slice().iter_mut()
+            .zip(other.backing.as_slice());
+        for (v, u) in pairs {
+            f(v, u);
+        }
+    }
+
+    /// Fold another map over the same domain into this one,
+    /// e.g. to accumulate partial results.
+    pub fn merge_from<S2, F>(&mut self, other: &Map<V, H, S2>, f: F)
+        where S2: Storage<V>, F: FnMut(&mut V, &V)
+    {
+        self.zip_assign(other, f)
+    }
+
+    /// Multiply every value by `factor`.
+    pub fn scale(&mut self, factor: V) where V: Copy + MulAssign {
+        for v in self.backing.as_mut_slice() {
+            *v *= factor;
+        }
+    }
+
+    /// Directly create a new iterator over the mutable values:
+    /// `Iterator<Item=&mut V>`.
+    pub fn values_mut(&mut self) -> ValuesMut<'_, H, V> {
+        let remaining = count_valid(&self.hash, 0..self.hash.size());
+        ValuesMut {
+            backing: self.backing.as_mut_slice().iter_mut(),
+            hash: &self.hash,
+            pos: 0,
+            remaining,
+        }
+    }
+}
+
+impl<V, H, S> Map<V, H, S> {
+    /// Get the hasher, e.g. to call `invert` or `size`.
+    /// There is no mutable access, as changing the hasher's domain
+    /// would invalidate the stored values.
+    pub fn hasher(&self) -> &H {
+        &self.hash
+    }
+
+    /// Decompose the map into its hasher and its storage.
+    /// `from_storage` reassembles them.
+    pub fn into_parts(self) -> (H, S) {
+        (self.hash, self.backing)
+    }
+}
+
+impl<V, H> Map<V, H> {
+    /// Consume the map, returning the values in hash order, holes included.
+    pub fn into_boxed_slice(self) -> Box<[V]> {
+        self.backing
+    }
+
+    /// Consume and leak the map, returning the values in hash order,
+    /// holes included.  This is useful for lookup tables that are built
+    /// once and then needed for the rest of the program.
+    pub fn leak(self) -> &'static mut [V] {
+        Box::leak(self.backing)
+    }
+
+    /// Like `leak`, but keeps the values accessible by key.
+    pub fn leak_map(self) -> LeakedMap<V, H> {
+        let Map { hash, backing, .. } = self;
+        Map {
+            hash,
+            backing: Box::leak(backing),
+            values: PhantomData,
+        }
+    }
+}
+
+impl<V, H, S: StorageMut<V>> Map<V, H, S> {
+    /// Get all values as a mutable slice, in hash order, holes included.
+    pub fn as_mut_slice(&mut self) -> &mut [V] {
+        self.backing.as_mut_slice()
+    }
+}
+
+impl<V, H, S: Storage<V>> Map<V, H, S> {
+    /// Get all values as a slice, in hash order, holes included.
+    /// This is useful for handing the dense values to other code,
+    /// e.g. serialization or vectorized kernels.
+    pub fn as_slice(&self) -> &[V] {
+        self.backing.as_slice()
+    }
+
+    /// Returns true if the map contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.backing.as_slice().is_empty()
+    }
+
+    /// Returns the amount of entries,
+    /// which is always equal to the hasher's domain (i.e., `hasher.size()`).
+    /// Note that this includes holes, if any.
+    pub fn len(&self) -> usize {
+        self.backing.as_slice().len()
+    }
+
+    /// The size of the values in bytes, holes included.
+    /// This does not account for the hasher, nor any heap memory
+    /// owned by the values themselves.
+    pub fn memory_usage(&self) -> usize {
+        std::mem::size_of_val(self.backing.as_slice())
+    }
+}
+
+/// Iterator over the entries of one column of a `Map` with a product
+/// hasher, see `Map::col`.
+pub struct ColIter<'a, H: 'a, V: 'a> {
+    backing: std::iter::StepBy<std::iter::Skip<std::slice::Iter<'a, V>>>,
+    hash: &'a H,
+    pos: usize,
+}
+
+impl<'a, H: HashInverse, V: 'a> Iterator for ColIter<'a, H, V> {
+    type Item = (H::K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for value in &mut self.backing {
+            let pos = self.pos;
+            self.pos += 1;
+            if self.hash.is_valid(pos) {
+                return Some((self.hash.invert(pos), value));
+            }
+        }
+        None
+    }
+}
+
+/// Iterator over the mutable entries of one column of a `Map` with a
+/// product hasher, see `Map::col_mut`.
+pub struct ColIterMut<'a, H: 'a, V: 'a> {
+    backing: std::iter::StepBy<std::iter::Skip<std::slice::IterMut<'a, V>>>,
+    hash: &'a H,
+    pos: usize,
+}
+
+impl<'a, H: HashInverse, V: 'a> Iterator for ColIterMut<'a, H, V> {
+    type Item = (H::K, &'a mut V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for value in &mut self.backing {
+            let pos = self.pos;
+            self.pos += 1;
+            if self.hash.is_valid(pos) {
+                return Some((self.hash.invert(pos), value));
+            }
+        }
+        None
+    }
+}
+
+/// The amount of valid hash values within `range`.
+fn count_valid<H: PerfectHash>(hash: &H, range: Range<usize>) -> usize {
+    if hash.is_minimal() {
+        range.len()
+    } else {
+        range.filter(|&i| hash.is_valid(i)).count()
+    }
+}
+
+/// Iterator over blocks of entries of a `Map`, see `Map::chunks`.
+pub struct Chunks<'a, H: 'a, V: 'a> {
+    backing: std::slice::Chunks<'a, V>,
+    hash: &'a H,
+    pos: usize,
+}
+
+impl<'a, H: HashInverse, V: 'a> Iterator for Chunks<'a, H, V> {
+    type Item = MapIter<'a, H, V>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let chunk = self.backing.next()?;
+        let pos = self.pos;
+        self.pos += chunk.len();
+        Some(MapIter { backing: chunk.iter(), hash: self.hash, pos })
+    }
+}
+
+/// Iterator over blocks of mutable entries of a `Map`,
+/// see `Map::chunks_mut`.
+pub struct ChunksMut<'a, H: 'a, V: 'a> {
+    backing: std::slice::ChunksMut<'a, V>,
+    hash: &'a H,
+    pos: usize,
+}
+
+impl<'a, H: HashInverse, V: 'a> Iterator for ChunksMut<'a, H, V> {
+    type Item = MapIterMut<'a, H, V>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let chunk = self.backing.next()?;
+        let pos = self.pos;
+        self.pos += chunk.len();
+        Some(MapIterMut { backing: chunk.iter_mut(), hash: self.hash, pos })
+    }
+}
+
+/// Iterator over the entries of a `Map` in the order of their values,
+/// see `Map::iter_sorted_by`.
+pub struct SortedIter<'a, H: 'a, V: 'a> {
+    order: std::vec::IntoIter<usize>,
+    backing: &'a [V],
+    hash: &'a H,
+}
+
+impl<'a, H: HashInverse, V: 'a> Iterator for SortedIter<'a, H, V> {
+    type Item = (H::K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let i = self.order.next()?;
+        Some((self.hash.invert(i), &self.backing[i]))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.order.size_hint()
+    }
+}
+
+impl<'a, H: HashInverse, V: 'a> ExactSizeIterator for SortedIter<'a, H, V> {}
+
+/// Iterator over the entries of a `Map` that do not hold the default
+/// value, see `Map::iter_non_default`.
+pub struct NonDefault<'a, H: 'a, V: 'a> {
+    backing: std::slice::Iter<'a, V>,
+    hash: &'a H,
+    pos: usize,
+    default: V,
+}
+
+impl<'a, H: HashInverse, V: PartialEq + 'a> Iterator for NonDefault<'a, H, V> {
+    type Item = (H::K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for value in &mut self.backing {
+            let pos = self.pos;
+            self.pos += 1;
+            if *value != self.default && self.hash.is_valid(pos) {
+                return Some((self.hash.invert(pos), value));
+            }
+        }
+        None
+    }
+}
+
+/// Iterator over the keys of a `Map` grouped by their values,
+/// see `Map::group_by_value`.
+pub struct GroupByValue<'a, H: 'a, V: 'a> {
+    groups: std::vec::IntoIter<(&'a V, Vec<usize>)>,
+    hash: &'a H,
+}
+
+impl<'a, H: HashInverse, V: 'a> Iterator for GroupByValue<'a, H, V> {
+    type Item = (&'a V, GroupKeys<'a, H>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (value, indices) = self.groups.next()?;
+        Some((value, GroupKeys { indices: indices.into_iter(), hash: self.hash }))
+    }
+}
+
+/// Iterator over the keys of one group, see `Map::group_by_value`.
+pub struct GroupKeys<'a, H: 'a> {
+    indices: std::vec::IntoIter<usize>,
+    hash: &'a H,
+}
+
+impl<'a, H: HashInverse> Iterator for GroupKeys<'a, H> {
+    type Item = H::K;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.indices.next().map(|i| self.hash.invert(i))
+    }
+}
+
+/// Iterator over the differing entries of two `Map`s, see `Map::diff`.
+pub struct Diff<'a, H: 'a, V: 'a> {
+    hash: &'a H,
+    left: &'a [V],
+    right: &'a [V],
+    pos: usize,
+}
+
+impl<'a, H: HashInverse, V: PartialEq + 'a> Iterator for Diff<'a, H, V> {
+    type Item = (H::K, &'a V, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.pos < self.left.len() {
+            let pos = self.pos;
+            self.pos += 1;
+            let (left, right) = (&self.left[pos], &self.right[pos]);
+            if self.hash.is_valid(pos) && left != right {
+                return Some((self.hash.invert(pos), left, right));
+            }
+        }
+        None
+    }
+}
+
+/// Iterator over the values of a `Map`, skipping holes.
+pub struct Values<'a, H: 'a, V: 'a> {
+    backing: std::slice::Iter<'a, V>,
+    hash: &'a H,
+    /// The hash value of the front of `backing`.
+    pos: usize,
+    /// The amount of valid slots in `backing`.  If this equals its length,
+    /// there are no holes left to skip.
+    remaining: usize,
+}
+
+impl<'a, H: PerfectHash, V: 'a> Values<'a, H, V> {
+    fn new(hash: &'a H, backing: &'a [V], pos: usize) -> Self {
+        Values {
+            remaining: count_valid(hash, pos..pos + backing.len()),
+            backing: backing.iter(),
+            hash,
+            pos,
+        }
+    }
+}
+
+impl<'a, H: PerfectHash, V: 'a> Iterator for Values<'a, H, V> {
+    type Item = &'a V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.remaining < self.backing.len()
+            && !self.hash.is_valid(self.pos)
+        {
+            self.backing.next();
+            self.pos += 1;
+        }
+        let value = self.backing.next()?;
+        self.pos += 1;
+        self.remaining -= 1;
+        Some(value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, H: PerfectHash, V: 'a> DoubleEndedIterator for Values<'a, H, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        while self.remaining < self.backing.len()
+            && !self.hash.is_valid(self.pos + self.backing.len() - 1)
+        {
+            self.backing.next_back();
+        }
+        let value = self.backing.next_back()?;
+        self.remaining -= 1;
+        Some(value)
+    }
+}
+
+impl<'a, H: PerfectHash, V: 'a> ExactSizeIterator for Values<'a, H, V> {}
+
+impl<'a, H: PerfectHash, V: 'a> FusedIterator for Values<'a, H, V> {}
+
+impl<'a, H, V: 'a> Clone for Values<'a, H, V> {
+    fn clone(&self) -> Self { Self {
+        backing: self.backing.clone(),
+        hash: self.hash,
+        pos: self.pos,
+        remaining: self.remaining,
+    }}
+}
+
+/// Owning iterator over the values of a `Map`, skipping holes.
+pub struct IntoValues<H, V> {
+    backing: std::vec::IntoIter<V>,
+    hash: H,
+    pos: usize,
+}
+
+impl<H: PerfectHash, V> Iterator for IntoValues<H, V> {
+    type Item = V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for value in &mut self.backing {
+            let pos = self.pos;
+            self.pos += 1;
+            if self.hash.is_valid(pos) {
+                return Some(value);
+            }
+        }
+        None
+    }
+}
+
+/// Iterator over the mutable values of a `Map`, skipping holes.
+pub struct ValuesMut<'a, H: 'a, V: 'a> {
+    backing: std::slice::IterMut<'a, V>,
+    hash: &'a H,
+    /// The hash value of the front of `backing`.
+    pos: usize,
+    /// The amount of valid slots in `backing`, see `Values`.
+    remaining: usize,
+}
+
+impl<'a, H: PerfectHash, V: 'a> Iterator for ValuesMut<'a, H, V> {
+    type Item = &'a mut V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.remaining < self.backing.len()
+            && !self.hash.is_valid(self.pos)
+        {
+            self.backing.next();
+            self.pos += 1;
+        }
+        let value = self.backing.next()?;
+        self.pos += 1;
+        self.remaining -= 1;
+        Some(value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, H: PerfectHash, V: 'a> DoubleEndedIterator for ValuesMut<'a, H, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        while self.remaining < self.backing.len()
+            && !self.hash.is_valid(self.pos + self.backing.len() - 1)
+        {
+            self.backing.next_back();
+        }
+        let value = self.backing.next_back()?;
+        self.remaining -= 1;
+        Some(value)
+    }
+}
+
+impl<'a, H: PerfectHash, V: 'a> ExactSizeIterator for ValuesMut<'a, H, V> {}
+
+impl<'a, H: PerfectHash, V: 'a> FusedIterator for ValuesMut<'a, H, V> {}
+
+/// The entries of a `Map` whose keys satisfy a predicate,
+/// see `Map::sub_view`.
+pub struct SubMapView<'a, V: 'a, H: 'a, P> {
+    backing: &'a [V],
+    hash: &'a H,
+    pred: P,
+}
+
+impl<'a, V: 'a, H: HashInverse, P: Fn(&H::K) -> bool> SubMapView<'a, V, H, P> {
+    /// Returns true if key `k` is part of the view.
+    pub fn contains_key(&self, k: &H::K) -> bool {
+        (self.pred)(k)
+    }
+
+    /// The value for key `k`, or `None` if it is not part of the view.
+    pub fn get(&self, k: H::K) -> Option<&'a V> {
+        if (self.pred)(&k) {
+            Some(&self.backing[self.hash.hash(k)])
+        } else {
+            None
+        }
+    }
+
+    /// Create a new iterator over the entries in the view:
+    /// `Iterator<Item=(K,&V)>`.
+    pub fn iter(&self) -> SubMapIter<'_, H, V, P> {
+        SubMapIter {
+            inner: MapIter {
+                backing: self.backing.iter(),
+                hash: self.hash,
+                pos: 0,
+            },
+            pred: &self.pred,
+        }
+    }
+}
+
+/// The mutable entries of a `Map` whose keys satisfy a predicate,
+/// see `Map::sub_view_mut`.
+pub struct SubMapViewMut<'a, V: 'a, H: 'a, P> {
+    backing: &'a mut [V],
+    hash: &'a H,
+    pred: P,
+}
+
+impl<'a, V: 'a, H: HashInverse, P: Fn(&H::K) -> bool> SubMapViewMut<'a, V, H, P> {
+    /// Returns true if key `k` is part of the view.
+    pub fn contains_key(&self, k: &H::K) -> bool {
+        (self.pred)(k)
+    }
+
+    /// The value for key `k`, or `None` if it is not part of the view.
+    pub fn get(&self, k: H::K) -> Option<&V> {
+        if (self.pred)(&k) {
+            Some(&self.backing[self.hash.hash(k)])
+        } else {
+            None
+        }
+    }
+
+    /// The value for key `k`, or `None` if it is not part of the view.
+    pub fn get_mut(&mut self, k: H::K) -> Option<&mut V> {
+        if (self.pred)(&k) {
+            Some(&mut self.backing[self.hash.hash(k)])
+        } else {
+            None
+        }
+    }
+
+    /// Create a new iterator over the entries in the view:
+    /// `Iterator<Item=(K,&V)>`.
+    pub fn iter(&self) -> SubMapIter<'_, H, V, P> {
+        SubMapIter {
+            inner: MapIter {
+                backing: self.backing.iter(),
+                hash: self.hash,
+                pos: 0,
+            },
+            pred: &self.pred,
+        }
+    }
+
+    /// Create a new iterator over the mutable entries in the view:
+    /// `Iterator<Item=(K,&mut V)>`.
+    pub fn iter_mut(&mut self) -> SubMapIterMut<'_, H, V, P> {
+        SubMapIterMut {
+            inner: MapIterMut {
+                backing: self.backing.iter_mut(),
+                hash: self.hash,
+                pos: 0,
+            },
+            pred: &self.pred,
+        }
+    }
+}
+
+/// Iterator over the entries of a `SubMapView` or `SubMapViewMut`.
+pub struct SubMapIter<'a, H: 'a, V: 'a, P: 'a> {
+    inner: MapIter<'a, H, V>,
+    pred: &'a P,
+}
+
+impl<'a, H, V, P> Iterator for SubMapIter<'a, H, V, P>
+    where H: HashInverse, V: 'a, P: Fn(&H::K) -> bool
+{
+    type Item = (H::K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let pred = self.pred;
+        self.inner.find(|e| pred(&e.0))
+    }
+}
+
+/// Iterator over the mutable entries of a `SubMapViewMut`.
+pub struct SubMapIterMut<'a, H: 'a, V: 'a, P: 'a> {
+    inner: MapIterMut<'a, H, V>,
+    pred: &'a P,
+}
+
+impl<'a, H, V, P> Iterator for SubMapIterMut<'a, H, V, P>
+    where H: HashInverse, V: 'a, P: Fn(&H::K) -> bool
+{
+    type Item = (H::K, &'a mut V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let pred = self.pred;
+        self.inner.find(|e| pred(&e.0))
+    }
+}
+
+/// Some of the mutable entries of a `Map`, see `Map::split_partitions`.
+pub struct Partition<'a, H: 'a, V: 'a> {
+    hash: &'a H,
+    /// Hash values and their values, in hash order.
+    entries: Vec<(usize, &'a mut V)>,
+}
+
+impl<'a, H: HashInverse, V: 'a> Partition<'a, H, V> {
+    /// The value for key `k`, or `None` if it belongs to another partition.
+    pub fn get(&self, k: H::K) -> Option<&V> {
+        let i = self.hash.hash(k);
+        let pos = self.entries.binary_search_by_key(&i, |e| e.0).ok()?;
+        Some(&*self.entries[pos].1)
+    }
+
+    /// The value for key `k`, or `None` if it belongs to another partition.
+    pub fn get_mut(&mut self, k: H::K) -> Option<&mut V> {
+        let i = self.hash.hash(k);
+        let pos = self.entries.binary_search_by_key(&i, |e| e.0).ok()?;
+        Some(&mut *self.entries[pos].1)
+    }
+
+    /// The amount of entries in this partition.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns true if this partition has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Create a new iterator over the mutable entries of this partition:
+    /// `Iterator<Item=(K,&mut V)>`.
+    pub fn iter_mut(&mut self) -> PartitionIterMut<'_, 'a, H, V> {
+        PartitionIterMut {
+            entries: self.entries.iter_mut(),
+            hash: self.hash,
+        }
+    }
+}
+
+/// Iterator over the mutable entries of a `Partition`.
+pub struct PartitionIterMut<'b, 'a: 'b, H: 'a, V: 'a> {
+    entries: std::slice::IterMut<'b, (usize, &'a mut V)>,
+    hash: &'a H,
+}
+
+impl<'b, 'a: 'b, H: HashInverse, V: 'a> Iterator for PartitionIterMut<'b, 'a, H, V> {
+    type Item = (H::K, &'b mut V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let entry = self.entries.next()?;
+        Some((self.hash.invert(entry.0), &mut *entry.1))
+    }
+}
+
+/// A cursor over the entries of a `Map`, see `Map::cursor_mut`.
+/// Like `std::collections::linked_list::CursorMut`, there is a "ghost"
+/// position after the last and before the first entry, where the cursor
+/// points to no entry.
+pub struct CursorMut<'a, H: 'a, V: 'a> {
+    backing: &'a mut [V],
+    hash: &'a H,
+    pos: usize,
+}
+
+impl<'a, H: HashInverse, V: 'a> CursorMut<'a, H, V> {
+    /// First valid hash value at or after `from`, or the ghost position.
+    fn next_valid(&self, from: usize) -> usize {
+        (from..self.backing.len())
+            .find(|&i| self.hash.is_valid(i))
+            .unwrap_or(self.backing.len())
+    }
+
+    /// Last valid hash value before `until`, or the ghost position.
+    fn prev_valid(&self, until: usize) -> usize {
+        (0..until).rev()
+            .find(|&i| self.hash.is_valid(i))
+            .unwrap_or(self.backing.len())
+    }
+
+    /// The key of the current entry, or `None` at the ghost position.
+    pub fn key(&self) -> Option<H::K> {
+        self.value().map(|_| self.hash.invert(self.pos))
+    }
+
+    /// The current value, or `None` at the ghost position.
+    pub fn value(&self) -> Option<&V> {
+        self.backing.get(self.pos)
+    }
+
+    /// The current value, or `None` at the ghost position.
+    pub fn value_mut(&mut self) -> Option<&mut V> {
+        self.backing.get_mut(self.pos)
+    }
+
+    /// Move to the next entry, or from the last entry to the
+    /// ghost position, or from the ghost position to the first entry.
+    pub fn move_next(&mut self) {
+        let from = if self.pos == self.backing.len() { 0 } else { self.pos + 1 };
+        self.pos = self.next_valid(from);
+    }
+
+    /// Move to the previous entry, or from the first entry to the
+    /// ghost position, or from the ghost position to the last entry.
+    pub fn move_prev(&mut self) {
+        self.pos = self.prev_valid(self.pos);
+    }
+
+    /// The next entry, without moving the cursor.
+    pub fn peek_next(&self) -> Option<(H::K, &V)> {
+        let from = if self.pos == self.backing.len() { 0 } else { self.pos + 1 };
+        let next = self.next_valid(from);
+        self.backing.get(next).map(|v| (self.hash.invert(next), v))
+    }
+
+    /// The previous entry, without moving the cursor.
+    pub fn peek_prev(&self) -> Option<(H::K, &V)> {
+        let prev = self.prev_valid(self.pos);
+        self.backing.get(prev).map(|v| (self.hash.invert(prev), v))
+    }
+
+    /// Swap the current value with the value for key `k`.
+    /// Panics at the ghost position.
+    pub fn swap_with(&mut self, k: H::K) {
+        assert!(self.pos < self.backing.len(), "Cursor is at the ghost position");
+        let other = self.hash.hash(k);
+        self.backing.swap(self.pos, other);
+    }
+}
+
+/// A single slot of a `Map`, see `Map::entry`.
+/// As a `Map` is always full, the slot is always occupied.
+pub struct Entry<'a, V: 'a> {
+    value: &'a mut V,
+}
+
+impl<'a, V: 'a> Entry<'a, V> {
+    /// Modify the stored value, and keep the entry for further use.
+    pub fn and_modify<F: FnOnce(&mut V)>(self, f: F) -> Self {
+        f(self.value);
+        self
+    }
+
+    /// Overwrite the stored value by `v`, and return the old value.
+    pub fn replace(self, v: V) -> V {
+        std::mem::replace(self.value, v)
+    }
+
+    /// Move the stored value out, leaving `V::default()` behind.
+    pub fn take(self) -> V where V: Default {
+        std::mem::take(self.value)
+    }
+
+    /// Get a reference to the stored value.
+    pub fn get(&self) -> &V {
+        self.value
+    }
+
+    /// Get a mutable reference to the stored value.
+    pub fn get_mut(&mut self) -> &mut V {
+        self.value
+    }
+
+    /// Convert the entry into a mutable reference to the stored value,
+    /// with the lifetime of the `Map`.
+    pub fn into_mut(self) -> &'a mut V {
+        self.value
+    }
+}
+
+impl<V, H: PerfectHash, S: StorageMut<V>> Extend<(H::K, V)> for Map<V, H, S> {
+    fn extend<I: IntoIterator<Item = (H::K, V)>>(&mut self, iter: I) {
+        for (k, v) in iter {
+            self.insert(k, v);
+        }
+    }
+}
+
+impl<V, H> FromIterator<(H::K, V)> for Map<V, H>
+    where V: Default, H: PerfectHash + Default
+{
+    fn from_iter<I: IntoIterator<Item = (H::K, V)>>(iter: I) -> Self {
+        Self::from_pairs(H::default(), iter)
+    }
+}
+
+impl<V: Default, H: PerfectHash + Default> Default for Map<V, H> {
+    fn default() -> Self {
+        Self::new(H::default())
+    }
+}
+
+/// Slot-by-slot addition of a map over the same domain.
+impl<'a, V, H, S, S2> AddAssign<&'a Map<V, H, S2>> for Map<V, H, S>
+    where V: Copy + AddAssign, H: PerfectHash, S: StorageMut<V>, S2: Storage<V>
+{
+    fn add_assign(&mut self, other: &'a Map<V, H, S2>) {
+        self.zip_assign(other, |v, &u| *v += u);
+    }
+}
+
+/// Slot-by-slot subtraction of a map over the same domain.
+impl<'a, V, H, S, S2> SubAssign<&'a Map<V, H, S2>> for Map<V, H, S>
+    where V: Copy + SubAssign, H: PerfectHash, S: StorageMut<V>, S2: Storage<V>
+{
+    fn sub_assign(&mut self, other: &'a Map<V, H, S2>) {
+        self.zip_assign(other, |v, &u| *v -= u);
+    }
+}
+
+/// Slot-by-slot addition of two maps over the same domain,
+/// reusing the left-hand side's storage.
+impl<V, H, S, S2> Add<Map<V, H, S2>> for Map<V, H, S>
+    where V: Copy + AddAssign, H: PerfectHash, S: StorageMut<V>, S2: Storage<V>
+{
+    type Output = Self;
+
+    fn add(mut self, other: Map<V, H, S2>) -> Self {
+        self += &other;
+        self
+    }
+}
+
+/// Slot-by-slot subtraction of two maps over the same domain,
+/// reusing the left-hand side's storage.
+impl<V, H, S, S2> Sub<Map<V, H, S2>> for Map<V, H, S>
+    where V: Copy + SubAssign, H: PerfectHash, S: StorageMut<V>, S2: Storage<V>
+{
+    type Output = Self;
+
+    fn sub(mut self, other: Map<V, H, S2>) -> Self {
+        self -= &other;
+        self
+    }
+}
+
+/// Frequency counting on maps with integer values, e.g. `counts.increment(k)`
+/// instead of `*counts.get_mut(k) += 1`.  All methods return the new count.
+pub trait Counter {
+    type Key;
+    type Count;
+
+    /// Add one to the count for key `k`.
+    fn increment(&mut self, k: Self::Key) -> Self::Count;
+
+    /// Add `n` to the count for key `k`.
+    fn add_count(&mut self, k: Self::Key, n: Self::Count) -> Self::Count;
+
+    /// Add `n` to the count for key `k`, stopping at the numeric bounds.
+    fn saturating_add(&mut self, k: Self::Key, n: Self::Count) -> Self::Count;
+}
+
+macro_rules! impl_counter {
+    ($($count:ty)*) => { $(
+        impl<H: PerfectHash, S: StorageMut<$count>> Counter for Map<$count, H, S> {
+            type Key = H::K;
+            type Count = $count;
+
+            fn increment(&mut self, k: H::K) -> $count {
+                self.add_count(k, 1)
+            }
+
+            fn add_count(&mut self, k: H::K, n: $count) -> $count {
+                let count = self.get_mut(k);
+                *count += n;
+                *count
+            }
+
+            fn saturating_add(&mut self, k: H::K, n: $count) -> $count {
+                let count = self.get_mut(k);
+                *count = count.saturating_add(n);
+                *count
+            }
+        }
+    )* };
+}
+
+impl_counter!(u8 u16 u32 u64 u128 usize i8 i16 i32 i64 i128 isize);
+
+/// Maps are equal if their hashers are equal and they hold equal values
+/// for all keys.  Holes are ignored.
+impl<V, H, S> PartialEq for Map<V, H, S>
+    where V: PartialEq, H: PerfectHash + PartialEq, S: Storage<V>
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.hash == other.hash && self.values().eq(other.values())
+    }
+}
+
+impl<V, H, S> Eq for Map<V, H, S>
+    where V: Eq, H: PerfectHash + Eq, S: Storage<V>
+{}
+
+impl<V, H, S> std::hash::Hash for Map<V, H, S>
+    where V: std::hash::Hash, H: PerfectHash + std::hash::Hash, S: Storage<V>
+{
+    fn hash<T: std::hash::Hasher>(&self, state: &mut T) {
+        std::hash::Hash::hash(&self.hash, state);
+        for v in self.values() {
+            v.hash(state);
+        }
+    }
+}
+
+impl<V, H, S> fmt::Debug for Map<V, H, S>
+    where V: fmt::Debug, H: PerfectHash, S: Storage<V>
+{
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_list().entries(self.values()).finish()
+    }
+}
+
+/// Debug formatting of the entries of a `Map`, see `Map::debug_entries`.
+pub struct DebugEntries<'a, V: 'a, H: 'a, S: 'a> {
+    map: &'a Map<V, H, S>,
+}
+
+impl<'a, V, H, S> fmt::Debug for DebugEntries<'a, V, H, S>
+    where V: fmt::Debug, H: HashInverse, H::K: fmt::Debug, S: Storage<V>
+{
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_map().entries(self.map.iter()).finish()
+    }
+}
+
+impl<V, H: Clone, S: Clone> Clone for Map<V, H, S> {
+    fn clone(&self) -> Self { Self {
+        hash: self.hash.clone(),
+        backing: self.backing.clone(),
+        values: PhantomData,
+    }}
+}
+
+impl<V, H: PerfectHash, S: Storage<V>> Index<H::K> for Map<V, H, S> {
+    type Output = V;
+
+    fn index(&self, k: H::K) -> &V {
+        self.get(k)
+    }
+}
+
+impl<V, H: PerfectHash, S: StorageMut<V>> IndexMut<H::K> for Map<V, H, S> {
+    fn index_mut(&mut self, k: H::K) -> &mut V {
+        self.get_mut(k)
+    }
+}
+
+/* A blanket `Index<&H::K>` would overlap with `Index<H::K>` under the
+ * coherence rules, so lookups by reference are implemented per hasher,
+ * for those that override `hash_ref`. */
+macro_rules! index_by_ref {
+    ($($hash:ty),*) => {$(
+        /// Look up a key by reference, through `PerfectHash::hash_ref`.
+        impl<'a, V, S: Storage<V>> Index<&'a <$hash as PerfectHash>::K>
+            for Map<V, $hash, S>
+        {
+            type Output = V;
+
+            fn index(&self, k: &'a <$hash as PerfectHash>::K) -> &V {
+                self.get_ref(k)
+            }
+        }
+
+        /// Look up a key by reference, through `PerfectHash::hash_ref`.
+        impl<'a, V, S: StorageMut<V>> IndexMut<&'a <$hash as PerfectHash>::K>
+            for Map<V, $hash, S>
+        {
+            fn index_mut(&mut self, k: &'a <$hash as PerfectHash>::K) -> &mut V {
+                self.get_ref_mut(k)
+            }
+        }
+    )*}
+}
+
+index_by_ref!(hashers::BoundedMultisets, hashers::CharRange,
+              hashers::Combinations, hashers::Ipv4Prefix, hashers::Kmer,
+              hashers::Permutations);
+
+/// Declare a `static` `Map`, in one of two forms.  Use values with
+/// interior mutability, e.g. atomics, to modify it afterwards.
+///
+/// Given an array of values in hash order, the map is an `ArrayMap`
+/// built at compile time through `ArrayMap::from_array`.  This needs
+/// a `StaticPerfectHash` that can be created in a `const` context,
+/// e.g. a unit struct like `Squares`.
+///
+/// Given key-value pairs, like `Map::from_pairs`, the map is *not* built
+/// at compile time, as hashing is not `const`.  Instead, it is built at
+/// runtime on first access, through a `std::sync::LazyLock`.
+///
+/// ```
+/// #[macro_use]
+/// extern crate phf_mut;
+/// use phf_mut::{ArrayMap, Map};
+/// use phf_mut::hashers::{Squares, UnorderedPairs};
+///
+/// static_map! {
+///     static WEIGHTS: ArrayMap<u8, Squares, 64> = Squares, [1; 64]
+/// }
+///
+/// static_map! {
+///     static DISTANCES: Map<u32, UnorderedPairs> = UnorderedPairs::new(3), {
+///         (0, 1) => 7,
+///         (1, 2) => 4,
+///     }
+/// }
+///
+/// fn main() {
+///     assert_eq!(64, WEIGHTS.values().map(|&w| w as u32).sum::<u32>());
+///     assert_eq!(7, DISTANCES[(1, 0)]);
+/// }
+/// ```
+#[macro_export]
+macro_rules! static_map {
+    ($(#[$attr:meta])* $vis:vis static $name:ident : $ty:ty = $hash:expr,
+     { $($k:expr => $v:expr),* $(,)? }) => {
+        $(#[$attr])*
+        $vis static $name: ::std::sync::LazyLock<$ty> =
+            ::std::sync::LazyLock::new(|| {
+                $crate::Map::from_pairs($hash, [$(($k, $v)),*])
+            });
+    };
+    ($(#[$attr:meta])* $vis:vis static $name:ident : $ty:ty = $hash:expr,
+     [ $($values:tt)* ]) => {
+        $(#[$attr])*
+        $vis static $name: $ty = $crate::ArrayMap::from_array($hash, [$($values)*]);
+    };
+}
+
+/// A `Map` paired with a `Set` of the keys that are present, which gives
+/// `HashMap`-like semantics on top of the dense backing: keys are only
+/// present after being inserted, and iteration only visits those.
+pub struct MaskedMap<V, H> {
+    map: Map<V, H>,
+    mask: Set<H>,
+}
+
+impl<V: Default, H: PerfectHash + Clone> MaskedMap<V, H> {
+    /// Create a new, empty `MaskedMap`.
+    pub fn new(hash: H) -> Self {
+        MaskedMap {
+            map: Map::new(hash.clone()),
+            mask: Set::new(hash),
+        }
+    }
+}
+
+impl<V, H: PerfectHash> MaskedMap<V, H> {
+    /// Insert `v` for key `k`, returning the previous value, if present.
+    pub fn insert(&mut self, k: H::K, v: V) -> Option<V> {
+        let idx = self.map.hash.hash(k);
+        let old = std::mem::replace(&mut self.map.backing[idx], v);
+        if self.mask.put(idx, true) {
+            Some(old)
+        } else {
+            None
+        }
+    }
+
+    /// Remove key `k`, returning its value, if present.
+    /// The slot is left holding `V::default()`.
+    pub fn remove(&mut self, k: H::K) -> Option<V> where V: Default {
+        let idx = self.map.hash.hash(k);
+        if self.mask.put(idx, false) {
+            Some(std::mem::take(&mut self.map.backing[idx]))
+        } else {
+            None
+        }
+    }
+
+    /// Returns whether key `k` is present.
+    pub fn contains_key(&self, k: H::K) -> bool {
+        self.mask.has(self.map.hash.hash(k))
+    }
+
+    /// The value for key `k`, if present.
+    pub fn get(&self, k: H::K) -> Option<&V> {
+        let idx = self.map.hash.hash(k);
+        if self.mask.has(idx) {
+            Some(&self.map.backing[idx])
+        } else {
+            None
+        }
+    }
+
+    /// The value for key `k`, if present.
+    pub fn get_mut(&mut self, k: H::K) -> Option<&mut V> {
+        let idx = self.map.hash.hash(k);
+        if self.mask.has(idx) {
+            Some(&mut self.map.backing[idx])
+        } else {
+            None
+        }
+    }
+
+    /// The amount of present keys.
+    pub fn len(&self) -> usize {
+        self.mask.len()
+    }
+
+    /// Returns true if no key is present.
+    pub fn is_empty(&self) -> bool {
+        self.mask.is_empty()
+    }
+
+    /// The underlying map, including the values of absent keys.
+    pub fn as_map(&self) -> &Map<V, H> {
+        &self.map
+    }
+
+    /// The set of present keys.
+    pub fn mask(&self) -> &Set<H> {
+        &self.mask
+    }
+}
+
+impl<V, H: HashInverse> MaskedMap<V, H> {
+    /// Create a new iterator over the present entries:
+    /// `Iterator<Item=(K,&V)>`.
+    pub fn iter(&self) -> MaskedIter<'_, H, V> {
+        MaskedIter {
+            backing: self.map.backing.iter(),
+            hash: &self.map.hash,
+            mask: &self.mask.backing,
+            pos: 0,
+        }
+    }
+}
+
+impl<V: Clone, H: Clone> Clone for MaskedMap<V, H> {
+    fn clone(&self) -> Self {
+        MaskedMap {
+            map: self.map.clone(),
+            mask: self.mask.clone(),
+        }
+    }
+}
+
+/// Iterator over the present entries of a `MaskedMap`.
+pub struct MaskedIter<'a, H: 'a, V: 'a> {
+    backing: std::slice::Iter<'a, V>,
+    hash: &'a H,
+    mask: &'a [u32],
+    pos: usize,
+}
+
+impl<'a, H: HashInverse, V: 'a> Iterator for MaskedIter<'a, H, V> {
+    type Item = (H::K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for value in &mut self.backing {
+            let pos = self.pos;
+            self.pos += 1;
+            if get_bit(self.mask, pos) {
+                return Some((self.hash.invert(pos), value));
+            }
+        }
+        None
+    }
+}
+
+const BLOCK_BITS: usize = u32::BITS as usize;
+
+/// The amount of `u32` blocks needed for `bits` bits.
+fn block_count(bits: usize) -> usize {
+    bits.div_ceil(BLOCK_BITS)
+}
+
+fn get_bit(blocks: &[u32], index: usize) -> bool {
+    blocks[index / BLOCK_BITS] & (1 << (index % BLOCK_BITS)) != 0
+}
+
+/// The amount of set bits.
+fn count_ones(blocks: &[u32]) -> usize {
+    blocks.iter().map(|b| b.count_ones() as usize).sum()
+}
+
+/// A mutable, perfectly-hashed set.  Note that a small domain is recommended.
+/// For sparse sets, you might prefer `std::collections::HashSet`.
+/// The bits live in a `Storage` of `u32` blocks, one bit per hash value,
+/// which is a boxed slice by default.
+pub struct Set<H, S = Box<[u32]>> {
+    hash: H,
+    backing: S,
+    /// The amount of set bits, so that `len` needs no scan.
+    len: usize,
+    /// The amount of valid hash values, i.e. `len` of a full set.
+    capacity: usize,
+}
+
+impl<H: PerfectHash> Set<H> {
+    /// Create a new, empty set.
+    pub fn new(hash: H) -> Self {
+        let size = hash.size();
+        Set {
+            capacity: count_valid(&hash, 0..hash.size()),
+            hash,
+            backing: vec![0; block_count(size)].into_boxed_slice(),
+            len: 0,
+        }
+    }
+}
+
+#[cfg(feature = "allocator_api")]
+impl<H: PerfectHash, A: Allocator> Set<H, Box<[u32], A>> {
+    /// Create a new, empty set, like `new`, but with the bits allocated
+    /// by `alloc`, e.g. in an arena or in huge pages.
+    /// Needs the `allocator_api` feature and a nightly compiler.
+    pub fn new_in(hash: H, alloc: A) -> Self {
+        let blocks = block_count(hash.size());
+        let mut backing = Vec::with_capacity_in(blocks, alloc);
+        backing.resize(blocks, 0);
+        Set {
+            capacity: count_valid(&hash, 0..hash.size()),
+            hash,
+            backing: backing.into_boxed_slice(),
+            len: 0,
+        }
+    }
+}
+
+impl<H: PerfectHash, S: Storage<u32>> Set<H, S> {
+    /// Reassemble a `Set` from the parts returned by `into_parts`,
+    /// or create one on top of existing `u32` blocks, one bit per hash
+    /// value.  Returns an error if the amount of blocks is not compatible
+    /// to the PerfectHash, or if the bit of a hole or past the end is set.
+    pub fn from_parts(hash: H, backing: S) -> Result<Self, InvalidBlocks> {
+        let size = hash.size();
+        let blocks = backing.as_slice();
+        let (expected, actual) = (block_count(size), blocks.len());
+        if expected != actual {
+            let err = WrongLength { expected, actual };
+            return Err(InvalidBlocks::WrongLength(err));
+        }
+        let stray = (0..actual * BLOCK_BITS)
+            .find(|&i| get_bit(blocks, i) && (i >= size || !hash.is_valid(i)));
+        if let Some(index) = stray {
+            return Err(InvalidBlocks::StrayBit(index));
+        }
+        let len = count_ones(blocks);
+        Ok(Set { capacity: count_valid(&hash, 0..hash.size()), hash, backing, len })
+    }
+
+    /// Get the hasher, e.g. to call `invert` or `size`.
+    pub fn hasher(&self) -> &H {
+        &self.hash
+    }
+
+    /// Decompose the set into its hasher and its blocks of bits,
+    /// also see `from_parts`.
+    pub fn into_parts(self) -> (H, S) {
+        (self.hash, self.backing)
+    }
+
+    fn has(&self, index: usize) -> bool {
+        get_bit(self.backing.as_slice(), index)
+    }
+
+    /// Returns whether the key is in the set.
+    pub fn contains(&self, k: H::K) -> bool {
+        let idx = self.hash.hash(k);
+        self.has(idx)
+    }
+
+    /// Like `contains`, but takes any form of the key that the hasher
+    /// can hash, e.g. `&str` instead of a `String`.
+    pub fn contains_by<Q: ?Sized>(&self, q: &Q) -> bool
+        where H: HashEquivalent<Q>
+    {
+        self.has(self.hash.hash_equivalent(q))
+    }
+
+    /// Like `contains`, but takes the key by reference.
+    /// This avoids cloning keys for hashers that implement `hash_ref`.
+    pub fn contains_ref(&self, k: &H::K) -> bool where H::K: Clone {
+        self.has(self.hash.hash_ref(k))
+    }
+
+    /// Returns the amount of keys in the set.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns the fraction of the hasher's domain that is in the set,
+    /// between 0.0 for an empty and 1.0 for a full set.  Holes, if any,
+    /// do not count towards the domain.  An empty domain has density 0.0.
+    pub fn density(&self) -> f64 {
+        match self.capacity {
+            0 => 0.0,
+            capacity => self.len as f64 / capacity as f64,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.len == self.capacity
+    }
+
+    /// Returns whether all keys of `self` are also in `other`.
+    /// Panics if the sets are over hashers of different size.
+    pub fn is_subset(&self, other: &Self) -> bool {
+        self.len <= other.len && self.all_blocks(other, |a, b| a & !b == 0)
+    }
+
+    /// Returns whether all keys of `other` are also in `self`.
+    /// Panics if the sets are over hashers of different size.
+    pub fn is_superset(&self, other: &Self) -> bool {
+        other.is_subset(self)
+    }
+
+    /// Returns whether `self` and `other` have no keys in common.
+    /// Panics if the sets are over hashers of different size.
+    pub fn is_disjoint(&self, other: &Self) -> bool {
+        self.all_blocks(other, |a, b| a & b == 0)
+    }
+
+    /// Returns whether `f` holds for all pairs of corresponding blocks,
+    /// stopping at the first one for which it does not.
+    fn all_blocks<F: FnMut(u32, u32) -> bool>(&self, other: &Self, mut f: F)
+        -> bool
+    {
+        assert_eq!(self.hash.size(), other.hash.size(),
+                   "Sets must be over hashers of the same size");
+        let (ours, theirs) = (self.backing.as_slice(), other.backing.as_slice());
+        ours.iter().zip(theirs).all(|(&a, &b)| f(a, b))
+    }
+
+    /// The size of the bit storage in bytes, which is about
+    /// one bit per hash value.  This does not account for the hasher.
+    pub fn memory_usage(&self) -> usize {
+        std::mem::size_of_val(self.backing.as_slice())
+    }
+}
+
+impl<H: PerfectHash, S: StorageMut<u32>> Set<H, S> {
+    /// Insert a key into the set, so that `contains`
+    /// for an equal key returns `true` in the future.
+    /// Returns whether this key already was in the set.
+    pub fn insert(&mut self, k: H::K) -> bool {
+        self.set(k, true)
+    }
+
+    /// Erases a key from the set, so that `contains`
+    /// for an equal key returns `false` in the future.
+    /// Returns whether this key already was in the set.
+    pub fn erase(&mut self, k: H::K) -> bool {
+        self.set(k, false)
+    }
+
+    /// Insert the key if `present` is true, otherwise erase it.
+    /// Returns whether this key already was in the set.
+    pub fn set(&mut self, k: H::K, present: bool) -> bool {
+        let idx = self.hash.hash(k);
+        self.put(idx, present)
+    }
+
+    /// Erase the key if it is in the set, otherwise insert it.
+    /// Returns whether this key already was in the set.
+    pub fn toggle(&mut self, k: H::K) -> bool {
+        let idx = self.hash.hash(k);
+        let present = self.has(idx);
+        self.put(idx, !present)
+    }
+
+    fn put(&mut self, index: usize, present: bool) -> bool {
+        let ret = self.has(index);
+        if ret != present {
+            self.put_bit(index, present);
+            if present {
+                self.len += 1;
+            } else {
+                self.len -= 1;
+            }
+        }
+        ret
+    }
+
+    /// Erase all keys from the set.
+    pub fn clear(&mut self) {
+        self.backing.as_mut_slice().fill(0);
+        self.len = 0;
+    }
+
+    /// Insert all keys of the domain into the set.
+    pub fn fill(&mut self) {
+        self.backing.as_mut_slice().fill(!0);
+        self.clear_holes();
+        self.len = self.capacity;
+    }
+
+    /// Erase all keys that are in the set, and insert all others.
+    fn complement(&mut self) {
+        for block in self.backing.as_mut_slice() {
+            *block = !*block;
+        }
+        self.clear_holes();
+        self.len = self.capacity - self.len;
+    }
+
+    /// Clear the bits of holes, and the unused bits of the last block.
+    fn clear_holes(&mut self) {
+        let size = self.hash.size();
+        if self.capacity != size {
+            for i in 0..size {
+                if !self.hash.is_valid(i) {
+                    self.put_bit(i, false);
+                }
+            }
+        }
+        let used = size % BLOCK_BITS;
+        if used != 0 {
+            if let Some(last) = self.backing.as_mut_slice().last_mut() {
+                *last &= (1 << used) - 1;
+            }
+        }
+    }
+
+    /// Insert all keys yielded by `iter`, like calling `insert` for each.
+    pub fn insert_all<I: IntoIterator<Item = H::K>>(&mut self, iter: I) {
+        for k in iter {
+            self.insert(k);
+        }
+    }
+
+    /// Erase all keys yielded by `iter`, like calling `erase` for each.
+    pub fn erase_all<I: IntoIterator<Item = H::K>>(&mut self, iter: I) {
+        for k in iter {
+            self.erase(k);
+        }
+    }
+
+    /// Create a new set with all keys that are in `self`, `other`, or both.
+    /// Panics if the sets are over hashers of different size.
+    pub fn union(&self, other: &Self) -> Self
+        where H: Clone, S: Clone
+    {
+        self.combine(other, |a, b| a | b)
+    }
+
+    /// Create a new set with all keys that are in both `self` and `other`.
+    /// Panics if the sets are over hashers of different size.
+    pub fn intersection(&self, other: &Self) -> Self
+        where H: Clone, S: Clone
+    {
+        self.combine(other, |a, b| a & b)
+    }
+
+    /// Create a new set with all keys that are in `self` but not in `other`.
+    /// Panics if the sets are over hashers of different size.
+    pub fn difference(&self, other: &Self) -> Self
+        where H: Clone, S: Clone
+    {
+        self.combine(other, |a, b| a & !b)
+    }
+
+    /// Create a new set with all keys that are in exactly one of
+    /// `self` and `other`.
+    /// Panics if the sets are over hashers of different size.
+    pub fn symmetric_difference(&self, other: &Self) -> Self
+        where H: Clone, S: Clone
+    {
+        self.combine(other, |a, b| a ^ b)
+    }
+
+    fn combine<F>(&self, other: &Self, op: F) -> Self
+        where H: Clone, S: Clone, F: FnMut(u32, u32) -> u32
+    {
+        let mut set = self.clone();
+        set.combine_with(other, op);
+        set
+    }
+
+    /// Insert all keys of `other`, like `union` but in place.
+    /// Panics if the sets are over hashers of different size.
+    pub fn union_with(&mut self, other: &Self) {
+        self.combine_with(other, |a, b| a | b);
+    }
+
+    /// Erase all keys that are not in `other`, like `intersection`
+    /// but in place.
+    /// Panics if the sets are over hashers of different size.
+    pub fn intersect_with(&mut self, other: &Self) {
+        self.combine_with(other, |a, b| a & b);
+    }
+
+    /// Erase all keys of `other`, like `difference` but in place.
+    /// Panics if the sets are over hashers of different size.
+    pub fn difference_with(&mut self, other: &Self) {
+        self.combine_with(other, |a, b| a & !b);
+    }
+
+    /// Toggle all keys of `other`, like `symmetric_difference`
+    /// but in place.
+    /// Panics if the sets are over hashers of different size.
+    pub fn symmetric_difference_with(&mut self, other: &Self) {
+        self.combine_with(other, |a, b| a ^ b);
+    }
+
+    fn combine_with<F: FnMut(u32, u32) -> u32>(&mut self, other: &Self, mut op: F) {
+        assert_eq!(self.hash.size(), other.hash.size(),
+                   "Sets must be over hashers of the same size");
+        let (ours, theirs) = (self.backing.as_mut_slice(), other.backing.as_slice());
+        for (a, &b) in ours.iter_mut().zip(theirs) {
+            *a = op(*a, b);
+        }
+        self.len = count_ones(self.backing.as_slice());
+    }
+
+    fn put_bit(&mut self, index: usize, present: bool) {
+        let block = &mut self.backing.as_mut_slice()[index / BLOCK_BITS];
+        let bit = 1 << (index % BLOCK_BITS);
+        if present {
+            *block |= bit;
+        } else {
+            *block &= !bit;
+        }
+    }
+}
+
+impl<H: HashInverse, S: Storage<u32>> Set<H, S> {
+    /// Create an iterator over the contained keys.
+    pub fn iter(&self) -> SetIter<'_, H, S> {
+        let size = self.hash.size();
+        SetIter {
+            next: size,
+            start: 0,
+            end: size,
+            set: self,
+        }
+    }
+}
+
+impl<H: HashInverse, S: StorageMut<u32>> Set<H, S> {
+    /// Create an iterator that erases and yields all keys of the set,
+    /// in hash order.  When the iterator is dropped, all remaining keys
+    /// are erased, too.
+    pub fn drain(&mut self) -> Drain<'_, H, S> {
+        Drain { next: 0, set: self }
+    }
+
+    /// Erase and return the key with the lowest hash in the set, if any.
+    /// This allows for simple worklist loops like
+    /// `while let Some(k) = set.pop() { ... }`.
+    pub fn pop(&mut self) -> Option<H::K> {
+        let (block, bits) = self.backing.as_slice().iter().enumerate()
+            .find(|&(_, &bits)| bits != 0)?;
+        let idx = block * BLOCK_BITS + bits.trailing_zeros() as usize;
+        self.put(idx, false);
+        Some(self.hash.invert(idx))
+    }
+
+    /// Erase all keys for which `f` returns false, in hash order.
+    pub fn retain<F: FnMut(H::K) -> bool>(&mut self, mut f: F) {
+        for i in 0..self.hash.size() {
+            if self.has(i) && !f(self.hash.invert(i)) {
+                self.put(i, false);
+            }
+        }
+    }
+}
+
+impl<H, S> Set<H, S>
+    where H: OrderedHash + HashInverse, H::K: Clone, S: Storage<u32>
+{
+    /// Create an iterator over the contained keys that lie within `range`,
+    /// in key order.  Like `BTreeSet::range`, but without any searching.
+    pub fn range<R: RangeBounds<H::K>>(&self, range: R) -> SetIter<'_, H, S> {
+        let indices = hash_range(&self.hash, range);
+        SetIter {
+            next: indices.end,
+            start: indices.start,
+            end: indices.end,
+            set: self,
+        }
+    }
+}
+
+impl<'a, H: HashInverse, S: Storage<u32>> IntoIterator for &'a Set<H, S> {
+    type Item = H::K;
+    type IntoIter = SetIter<'a, H, S>;
+
+    fn into_iter(self) -> SetIter<'a, H, S> {
+        self.iter()
+    }
+}
+
+impl<H: PerfectHash + Default> Default for Set<H> {
+    fn default() -> Self {
+        Self::new(H::default())
+    }
+}
+
+impl<H: Clone, S: Clone> Clone for Set<H, S> {
+    fn clone(&self) -> Self { Self {
+        hash: self.hash.clone(),
+        backing: self.backing.clone(),
+        len: self.len,
+        capacity: self.capacity,
+    }}
+}
+
+macro_rules! impl_set_op {
+    ($op:ident $method:ident, $assign:ident $assign_method:ident, $with:ident,
+     $doc:expr) => {
+        #[doc = $doc]
+        impl<'a, H: PerfectHash, S: StorageMut<u32>> $assign<&'a Set<H, S>> for Set<H, S> {
+            fn $assign_method(&mut self, other: &'a Set<H, S>) {
+                self.$with(other);
+            }
+        }
+
+        #[doc = $doc]
+        impl<H: PerfectHash, S: StorageMut<u32>> $op<Set<H, S>> for Set<H, S> {
+            type Output = Set<H, S>;
+
+            fn $method(mut self, other: Set<H, S>) -> Set<H, S> {
+                self.$with(&other);
+                self
+            }
+        }
+
+        #[doc = $doc]
+        impl<'a, H: PerfectHash, S: StorageMut<u32>> $op<&'a Set<H, S>> for Set<H, S> {
+            type Output = Set<H, S>;
+
+            fn $method(mut self, other: &'a Set<H, S>) -> Set<H, S> {
+                self.$with(other);
+                self
+            }
+        }
+
+        #[doc = $doc]
+        impl<'a, H: PerfectHash, S: StorageMut<u32>> $op<Set<H, S>> for &'a Set<H, S> {
+            type Output = Set<H, S>;
+
+            fn $method(self, mut other: Set<H, S>) -> Set<H, S> {
+                other.$with(self);
+                other
+            }
+        }
+
+        #[doc = $doc]
+        impl<'a, 'b, H: PerfectHash + Clone, S: StorageMut<u32> + Clone> $op<&'b Set<H, S>> for &'a Set<H, S> {
+            type Output = Set<H, S>;
+
+            fn $method(self, other: &'b Set<H, S>) -> Set<H, S> {
+                let mut set = self.clone();
+                set.$with(other);
+                set
+            }
+        }
+    };
+}
+
+impl_set_op!(BitOr bitor, BitOrAssign bitor_assign, union_with,
+             "The union of two sets over the same hasher, see `Set::union`.");
+impl_set_op!(BitAnd bitand, BitAndAssign bitand_assign, intersect_with,
+             "The intersection of two sets over the same hasher, \
+              see `Set::intersection`.");
+impl_set_op!(BitXor bitxor, BitXorAssign bitxor_assign,
+             symmetric_difference_with,
+             "The symmetric difference of two sets over the same hasher, \
+              see `Set::symmetric_difference`.");
+
+/// The complement of a set, i.e. all other keys of the domain.
+impl<H: PerfectHash, S: StorageMut<u32>> Not for Set<H, S> {
+    type Output = Set<H, S>;
+
+    fn not(mut self) -> Set<H, S> {
+        self.complement();
+        self
+    }
+}
+
+/// The complement of a set, i.e. all other keys of the domain.
+impl<H, S> Not for &Set<H, S>
+    where H: PerfectHash + Clone, S: StorageMut<u32> + Clone
+{
+    type Output = Set<H, S>;
+
+    fn not(self) -> Set<H, S> {
+        !self.clone()
+    }
+}
+
+pub struct SetIter<'a, H: PerfectHash + 'a, S: 'a = Box<[u32]>> {
+    next: usize,
+    start: usize,
+    end: usize,
+    set: &'a Set<H, S>,
+}
+
+impl<'a, H: HashInverse, S: Storage<u32>> Iterator for SetIter<'a, H, S> {
+    type Item = H::K;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next == self.end {
+            /* Fresh start, or wrapped. */
+            self.next = self.start;
+        } else {
+            self.next += 1;
+        }
+        while self.next < self.end && !self.set.has(self.next) {
+            self.next += 1;
+        }
+        if self.next == self.end {
+            None
         } else {
             Some(self.set.hash.invert(self.next))
         }
     }
 }
+
+/// Iterator that erases and yields all keys of a `Set`, see `Set::drain`.
+pub struct Drain<'a, H: PerfectHash + 'a, S: StorageMut<u32> + 'a = Box<[u32]>> {
+    next: usize,
+    set: &'a mut Set<H, S>,
+}
+
+impl<'a, H: HashInverse, S: StorageMut<u32>> Iterator for Drain<'a, H, S> {
+    type Item = H::K;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.set.len > 0 && self.next < self.set.hash.size() {
+            let idx = self.next;
+            self.next += 1;
+            if self.set.put(idx, false) {
+                return Some(self.set.hash.invert(idx));
+            }
+        }
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.set.len, Some(self.set.len))
+    }
+}
+
+impl<'a, H: HashInverse, S: StorageMut<u32>> ExactSizeIterator for Drain<'a, H, S> {}
+
+impl<'a, H: PerfectHash, S: StorageMut<u32>> Drop for Drain<'a, H, S> {
+    fn drop(&mut self) {
+        self.set.clear();
+    }
+}
@@ -14,149 +14,2022 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
+#![allow(clippy::bool_assert_comparison)]
+
 use std::clone::Clone;
-use {PerfectHash, HashInverse, Map, Set};
+use std::sync::atomic::AtomicU32;
+use {Counter, DynHash, PerfectHash, HashEquivalent, HashInverse, StaticPerfectHash,
+     ArrayMap, LeakedMap, Map, MapView, MaskedMap, MapViewMut, Set, SubMapView,
+     InvalidBlocks, WrongLength};
+use hashers::UnorderedPairs as Pairs;
+use hashers::{AminoAcids, CharRange, Codons, Ipv4Prefix, Kmer, MidiNotes,
+              Sudoku, TimeOfDay};
+use hashers::{EnumHash, Enumerable};
+use hashers::{Card, Cards, CardsWithJokers, Rank, Suit};
+use hashers::{Color, Piece, PieceKind, PieceSquares, Pieces, Square, Squares};
+use hashers::{BoundedMultisets, Combinations, Grid2, Grid3, HexGrid,
+              OrderedPairs,
+              Permutations, PowerSet, StencilEdge, Torus2, ZOrder};
+
+/* === Helper hashers === */
+
+#[derive(Clone)]
+struct Upto {
+    n: usize,
+}
+
+impl Upto {
+    pub fn new(n: usize) -> Self {
+        Upto { n }
+    }
+}
+
+impl PerfectHash for Upto {
+    type K = usize;
+
+    fn hash(&self, k: Self::K) -> usize {
+        assert!(k < self.n);
+        k
+    }
+
+    fn size(&self) -> usize {
+        self.n
+    }
+}
+
+impl HashInverse for Upto {
+    fn invert(&self, index: usize) -> Self::K {
+        assert!(index < self.n);
+        index
+    }
+}
+
+/// Even numbers below `n`, using the number itself as the hash.
+/// Not minimal, as all odd indices are holes.
+#[derive(Clone)]
+struct Evens {
+    n: usize,
+}
+
+impl PerfectHash for Evens {
+    type K = usize;
+
+    fn hash(&self, k: Self::K) -> usize {
+        assert!(k < self.n && k.is_multiple_of(2));
+        k
+    }
+
+    fn size(&self) -> usize {
+        self.n
+    }
+
+    fn is_valid(&self, hash: usize) -> bool {
+        hash < self.n && hash.is_multiple_of(2)
+    }
+
+    fn is_minimal(&self) -> bool {
+        false
+    }
+}
+
+impl HashInverse for Evens {
+    fn invert(&self, index: usize) -> Self::K {
+        assert!(self.is_valid(index));
+        index
+    }
+}
+
+/* === Actual tests: default implementations === */
+
+#[test]
+fn test_domain_iter() {
+    let pairs = Pairs::new(4);
+    let actual = pairs.iter().collect::<Vec<_>>();
+    let expected = vec![(0, 0),
+                        (0, 1), (1, 1),
+                        (0, 2), (1, 2), (2, 2),
+                        (0, 3), (1, 3), (2, 3), (3, 3)];
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn test_tuple_pair() {
+    let hash = (Upto::new(2), Pairs::new(2));
+    assert_eq!(6, hash.size());
+    let actual = hash.iter().collect::<Vec<_>>();
+    let expected = vec![(0, (0, 0)), (0, (0, 1)), (0, (1, 1)),
+                        (1, (0, 0)), (1, (0, 1)), (1, (1, 1))];
+    assert_eq!(actual, expected);
+    for (i, k) in actual.into_iter().enumerate() {
+        assert_eq!(i, hash.hash(k));
+    }
+    assert_eq!(4, hash.hash((1, (1, 0))));
+}
+
+#[test]
+fn test_tuple_triple() {
+    let hash = (Upto::new(2), Upto::new(3), Upto::new(4));
+    assert_eq!(24, hash.size());
+    for (i, k) in hash.iter().enumerate() {
+        assert_eq!(i, hash.hash(k));
+    }
+    assert_eq!((1, 2, 3), hash.invert(23));
+    assert_eq!((1, 0, 2), hash.invert(14));
+    assert!(hash.is_valid(23));
+    assert!(!hash.is_valid(24));
+
+    let empty = (Upto::new(2), Upto::new(3), Upto::new(0));
+    assert_eq!(0, empty.size());
+    assert!(!empty.is_valid(0));
+    assert_eq!(0, empty.iter().count());
+}
+
+#[test]
+fn test_tuple_map() {
+    let mut mymap = Map::new((Upto::new(3), Upto::new(5)));
+    mymap[(2, 4)] = 42;
+    mymap.insert((0, 1), 7);
+    assert_eq!(42, mymap[(2, 4)]);
+    assert_eq!(7, mymap[(0, 1)]);
+    assert_eq!(0, mymap[(1, 0)]);
+    assert_eq!(15, mymap.len());
+}
+
+#[test]
+fn test_unordered_pairs_invert() {
+    let pairs = Pairs::new(300);
+    assert_eq!(300 * 301 / 2, pairs.size());
+    for i in 0..pairs.size() {
+        let (a, b) = pairs.invert(i);
+        assert!(a <= b && b < 300);
+        assert_eq!(i, pairs.hash((a, b)));
+        assert_eq!(i, pairs.hash((b, a)));
+    }
+}
+
+#[test]
+fn test_unordered_pairs_huge() {
+    let n = 1 << 30;
+    let pairs = Pairs::new(n);
+    assert_eq!((n - 1, n - 1), pairs.invert(pairs.size() - 1));
+    assert_eq!((0, n - 1), pairs.invert(pairs.hash((n - 1, 0))));
+    assert_eq!((12345, 67890), pairs.invert(pairs.hash((67890, 12345))));
+}
+
+#[test]
+fn test_ordered_pairs() {
+    let pairs = OrderedPairs::new(3);
+    assert_eq!(9, pairs.size());
+    assert_eq!(5, pairs.hash((1, 2)));
+    assert_eq!(7, pairs.hash((2, 1)));
+    for (i, k) in pairs.iter().enumerate() {
+        assert_eq!(i, pairs.hash(k));
+    }
+}
+
+#[test]
+fn test_ordered_pairs_without_diagonal() {
+    let pairs = OrderedPairs::without_diagonal(3);
+    assert_eq!(6, pairs.size());
+    let actual = pairs.iter().collect::<Vec<_>>();
+    let expected = vec![(0, 1), (0, 2), (1, 0), (1, 2), (2, 0), (2, 1)];
+    assert_eq!(actual, expected);
+    for (i, k) in actual.into_iter().enumerate() {
+        assert_eq!(i, pairs.hash(k));
+    }
+}
+
+#[test]
+#[should_panic]
+fn test_ordered_pairs_diagonal_panics() {
+    OrderedPairs::without_diagonal(3).hash((1, 1));
+}
+
+#[test]
+fn test_combinations() {
+    let comb = Combinations::new(5, 3);
+    assert_eq!(10, comb.size());
+    let actual = comb.iter().collect::<Vec<_>>();
+    let expected = vec![vec![0, 1, 2], vec![0, 1, 3], vec![0, 2, 3],
+                        vec![1, 2, 3], vec![0, 1, 4], vec![0, 2, 4],
+                        vec![1, 2, 4], vec![0, 3, 4], vec![1, 3, 4],
+                        vec![2, 3, 4]];
+    assert_eq!(actual, expected);
+    for (i, k) in actual.into_iter().enumerate() {
+        assert_eq!(i, comb.hash(k));
+    }
+    assert_eq!(7, comb.hash(vec![4, 0, 3]));
+}
+
+#[test]
+fn test_combinations_cards() {
+    let hands = Combinations::new(52, 5);
+    assert_eq!(2_598_960, hands.size());
+    let last = hands.invert(hands.size() - 1);
+    assert_eq!(vec![47, 48, 49, 50, 51], last);
+    let hand = vec![51, 3, 17, 42, 8];
+    let mut sorted = hand.clone();
+    sorted.sort();
+    assert_eq!(sorted, hands.invert(hands.hash(hand)));
+}
+
+#[test]
+fn test_combinations_large() {
+    assert_eq!(1_832_624_140_942_590_534, Combinations::new(64, 32).size());
+    let comb = Combinations::new(67, 33);
+    assert_eq!(14_226_520_737_620_288_370, comb.size());
+    assert_eq!((34..67).collect::<Vec<_>>(), comb.invert(comb.size() - 1));
+}
+
+#[test]
+#[should_panic]
+fn test_combinations_too_large() {
+    Combinations::new(68, 34).size();
+}
+
+#[test]
+#[should_panic]
+fn test_combinations_duplicate() {
+    Combinations::new(5, 3).hash(vec![1, 3, 1]);
+}
+
+#[test]
+fn test_permutations() {
+    let perms = Permutations::new(3);
+    assert_eq!(6, perms.size());
+    let actual = perms.iter().collect::<Vec<_>>();
+    let expected = vec![vec![0, 1, 2], vec![0, 2, 1], vec![1, 0, 2],
+                        vec![1, 2, 0], vec![2, 0, 1], vec![2, 1, 0]];
+    assert_eq!(actual, expected);
+    for (i, k) in actual.into_iter().enumerate() {
+        assert_eq!(i, perms.hash(k));
+    }
+}
+
+#[test]
+fn test_permutations_roundtrip() {
+    let perms = Permutations::new(8);
+    assert_eq!(40320, perms.size());
+    for i in (0..perms.size()).step_by(97) {
+        assert_eq!(i, perms.hash(perms.invert(i)));
+    }
+    assert_eq!(vec![7, 6, 5, 4, 3, 2, 1, 0], perms.invert(40319));
+}
+
+#[test]
+#[should_panic]
+fn test_permutations_invalid() {
+    Permutations::new(3).hash(vec![0, 2, 2]);
+}
+
+#[test]
+fn test_bounded_multisets() {
+    let multisets = BoundedMultisets::new(vec![1, 2, 0, 3]);
+    assert_eq!(24, multisets.size());
+    assert_eq!(0, multisets.hash(vec![0, 0, 0, 0]));
+    assert_eq!(23, multisets.hash(vec![1, 2, 0, 3]));
+    assert_eq!(vec![0, 1, 0, 2], multisets.invert(6));
+    for (i, k) in multisets.iter().enumerate() {
+        assert_eq!(i, multisets.hash(k));
+    }
+}
+
+#[test]
+#[should_panic]
+fn test_bounded_multisets_excess() {
+    BoundedMultisets::new(vec![1, 2]).hash(vec![2, 0]);
+}
+
+#[test]
+fn test_power_set() {
+    let subsets: PowerSet = PowerSet::new(3);
+    assert_eq!(8, subsets.size());
+    assert_eq!(5, subsets.hash(0b101));
+    assert_eq!((0..8).collect::<Vec<u32>>(), subsets.iter().collect::<Vec<_>>());
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Toppings(u32);
+
+impl From<Toppings> for u32 {
+    fn from(t: Toppings) -> u32 {
+        t.0
+    }
+}
+
+impl From<u32> for Toppings {
+    fn from(bits: u32) -> Toppings {
+        Toppings(bits)
+    }
+}
+
+#[test]
+fn test_power_set_flags() {
+    let mut prices = Map::new(PowerSet::<Toppings>::new(4));
+    prices.insert(Toppings(0b0011), 7);
+    assert_eq!(7, prices[Toppings(0b0011)]);
+    assert_eq!(Toppings(0b1111), PowerSet::<Toppings>::new(4).invert(15));
+}
+
+#[test]
+#[should_panic]
+fn test_power_set_out_of_range() {
+    PowerSet::<u32>::new(3).hash(0b1000);
+}
+
+#[test]
+fn test_grid2() {
+    let grid = Grid2::new(4, 3);
+    assert_eq!(12, grid.size());
+    assert_eq!(6, grid.hash((2, 1)));
+    assert_eq!((3, 2), grid.invert(11));
+    for (i, k) in grid.iter().enumerate() {
+        assert_eq!(i, grid.hash(k));
+    }
+    assert!(grid.contains((3, 2)));
+    assert!(!grid.contains((4, 0)));
+}
+
+#[test]
+fn test_grid2_neighbors() {
+    let grid = Grid2::new(4, 3);
+    assert_eq!(vec![(1, 0), (0, 1)], grid.neighbors4((0, 0)).collect::<Vec<_>>());
+    assert_eq!(vec![(1, 0), (0, 1), (1, 1)],
+               grid.neighbors8((0, 0)).collect::<Vec<_>>());
+    assert_eq!(vec![(2, 0), (1, 1), (3, 1), (2, 2)],
+               grid.neighbors4((2, 1)).collect::<Vec<_>>());
+    assert_eq!(8, grid.neighbors8((2, 1)).count());
+    assert_eq!(3, grid.neighbors8((3, 2)).count());
+}
+
+#[test]
+fn test_grid2_stencil() {
+    let grid = Grid2::new(3, 2);
+    let map = Map::from_fn(grid, |(x, y)| 10 * y + x);
+    let sum = |n: [[&usize; 3]; 3]| n.iter().flatten().copied().sum::<usize>();
+
+    let clamped = map.stencil(StencilEdge::Clamp).collect::<Vec<_>>();
+    assert_eq!(6, clamped.len());
+    let ((x, y), n) = clamped[0];
+    assert_eq!((0, 0), (x, y));
+    assert_eq!([[&0, &0, &1], [&0, &0, &1], [&10, &10, &11]], n);
+    assert_eq!(3 + 2 * 33, sum(clamped[4].1));
+
+    let wrapped = map.stencil(StencilEdge::Wrap).collect::<Vec<_>>();
+    assert_eq!([[&12, &10, &11], [&2, &0, &1], [&12, &10, &11]], wrapped[0].1);
+    assert_eq!(2 * 3 + 33, sum(wrapped[5].1));
+
+    let empty: Map<u8, _> = Map::new(Grid2::new(0, 0));
+    assert_eq!(0, empty.stencil(StencilEdge::Wrap).len());
+}
+
+#[test]
+fn test_grid3() {
+    let grid = Grid3::new(4, 3, 2);
+    assert_eq!(24, grid.size());
+    assert_eq!(4 * 3 + 2 * 4 + 1, grid.hash((1, 2, 1)));
+    assert_eq!((1, 2, 1), grid.invert(21));
+    for (i, k) in grid.iter().enumerate() {
+        assert_eq!(i, grid.hash(k));
+    }
+    assert!(grid.contains((3, 2, 1)));
+    assert!(!grid.contains((0, 0, 2)));
+}
+
+#[test]
+fn test_grid3_neighbors() {
+    let grid = Grid3::new(3, 3, 3);
+    assert_eq!(vec![(1, 0, 0), (0, 1, 0), (0, 0, 1)],
+               grid.neighbors6((0, 0, 0)).collect::<Vec<_>>());
+    assert_eq!(7, grid.neighbors26((0, 0, 0)).count());
+    assert_eq!(6, grid.neighbors6((1, 1, 1)).count());
+    assert_eq!(26, grid.neighbors26((1, 1, 1)).count());
+    assert_eq!(17, grid.neighbors26((1, 1, 2)).count());
+}
+
+#[test]
+fn test_torus2() {
+    let torus = Torus2::new(4, 3);
+    assert_eq!(12, torus.size());
+    assert_eq!(torus.hash((1, 2)), torus.hash((5, -1)));
+    assert_eq!(torus.hash((3, 0)), torus.hash((-1, 3)));
+    assert_eq!((3, 2), torus.invert(11));
+    for (i, k) in torus.iter().enumerate() {
+        assert_eq!(i, torus.hash(k));
+    }
+}
+
+#[test]
+fn test_torus2_neighbors() {
+    let torus = Torus2::new(4, 3);
+    assert_eq!(vec![(0, 2), (3, 0), (1, 0), (0, 1)],
+               torus.neighbors4((0, 0)).collect::<Vec<_>>());
+    assert_eq!(8, torus.neighbors8((-5, 7)).len());
+}
+
+#[test]
+fn test_hex_grid() {
+    assert_eq!(1, HexGrid::new(0).size());
+    assert_eq!((0, 0), HexGrid::new(0).invert(0));
+    let hex = HexGrid::new(2);
+    assert_eq!(19, hex.size());
+    let actual = hex.iter().collect::<Vec<_>>();
+    assert_eq!(&[(0, -2), (1, -2), (2, -2), (-1, -1), (0, -1)], &actual[..5]);
+    assert_eq!((0, 0), actual[9]);
+    assert_eq!(&[(-2, 2), (-1, 2), (0, 2)], &actual[16..]);
+    for (i, k) in actual.into_iter().enumerate() {
+        assert!(hex.contains(k));
+        assert_eq!(i, hex.hash(k));
+    }
+    assert!(!hex.contains((2, 1)));
+}
+
+#[test]
+fn test_hex_grid_neighbors() {
+    let hex = HexGrid::new(2);
+    assert_eq!(6, hex.neighbors((0, 0)).count());
+    assert_eq!(6, hex.neighbors((1, -1)).count());
+    assert_eq!(vec![(-1, 0), (-1, -1), (-2, 1)],
+               hex.neighbors((-2, 0)).collect::<Vec<_>>());
+}
+
+#[test]
+fn test_zorder_square() {
+    let z = ZOrder::new(Grid2::new(4, 4));
+    assert_eq!(16, z.size());
+    let actual = z.iter().take(8).collect::<Vec<_>>();
+    let expected = vec![(0, 0), (1, 0), (0, 1), (1, 1),
+                        (2, 0), (3, 0), (2, 1), (3, 1)];
+    assert_eq!(actual, expected);
+    assert_eq!(15, z.hash((3, 3)));
+    let mut seen = Set::new(Grid2::new(4, 4));
+    for (i, k) in z.iter().enumerate() {
+        assert_eq!(i, z.hash(k));
+        assert_eq!(false, seen.insert(k));
+    }
+    assert_eq!(true, seen.is_full());
+}
+
+#[test]
+fn test_zorder_rectangular() {
+    for &(w, h) in &[(8, 2), (2, 8), (1, 4), (16, 1)] {
+        let z = ZOrder::new(Grid2::new(w, h));
+        assert_eq!(w * h, z.size());
+        for (i, k) in z.iter().enumerate() {
+            assert!(Grid2::new(w, h).contains(k));
+            assert_eq!(i, z.hash(k));
+        }
+    }
+    let z = ZOrder::new(Torus2::new(4, 4));
+    assert_eq!(z.hash((3, 3)), z.hash((-1, -1)));
+}
+
+#[test]
+#[should_panic]
+fn test_zorder_not_power_of_two() {
+    ZOrder::new(Grid2::new(4, 3));
+}
+
+#[test]
+fn test_chess_squares() {
+    assert_eq!(64, Squares.size());
+    assert_eq!(0, Squares.hash(Square::new(0, 0)));
+    assert_eq!(28, Squares.hash(Square::new(4, 3)));
+    assert_eq!(Square::new(7, 7), Squares.invert(63));
+    for (i, k) in Squares.iter().enumerate() {
+        assert_eq!(i, Squares.hash(k));
+    }
+}
+
+#[test]
+fn test_chess_pieces() {
+    let black_queen = Piece::new(Color::Black, PieceKind::Queen);
+    assert_eq!(12, Pieces.size());
+    assert_eq!(10, Pieces.hash(black_queen));
+    for (i, k) in Pieces.iter().enumerate() {
+        assert_eq!(i, Pieces.hash(k));
+    }
+    let mut table = Map::new((Pieces, Squares) as PieceSquares);
+    table[(black_queen, Square::new(3, 7))] = 900;
+    assert_eq!(768, table.len());
+    assert_eq!(900, table[(black_queen, Square::new(3, 7))]);
+    assert_eq!(10 * 64 + 59, table.iter().position(|(_, &v)| v == 900).unwrap());
+}
+
+#[test]
+fn test_chess_bitboard() {
+    let mut board = Set::new(Squares);
+    board.insert(Square::new(0, 0));
+    board.insert(Square::new(4, 3));
+    board.insert(Square::new(7, 7));
+    let bits = board.bitboard();
+    assert_eq!((1 << 0) | (1 << 28) | (1 << 63), bits);
+    let again = Set::from_bitboard(bits);
+    assert_eq!(board.iter().collect::<Vec<_>>(), again.iter().collect::<Vec<_>>());
+    assert_eq!(true, Set::from_bitboard(!0).is_full());
+}
+
+#[test]
+fn test_cards() {
+    assert_eq!(52, Cards.size());
+    assert_eq!(0, Cards.hash((Suit::Clubs, Rank::Two)));
+    assert_eq!(51, Cards.hash((Suit::Spades, Rank::Ace)));
+    assert_eq!((Suit::Hearts, Rank::Jack), Cards.invert(35));
+    for (i, k) in Cards.iter().enumerate() {
+        assert_eq!(i, Cards.hash(k));
+    }
+}
+
+#[test]
+fn test_cards_with_jokers() {
+    let deck = CardsWithJokers::new(2);
+    assert_eq!(54, deck.size());
+    assert_eq!(Card::Regular(Suit::Diamonds, Rank::Two), deck.invert(13));
+    assert_eq!(Card::Joker(1), deck.invert(53));
+    for (i, k) in deck.iter().enumerate() {
+        assert_eq!(i, deck.hash(k));
+    }
+    let mut drawn = Set::new(deck);
+    drawn.insert(Card::Joker(0));
+    assert_eq!(vec![Card::Joker(0)], drawn.iter().collect::<Vec<_>>());
+}
+
+#[test]
+fn test_kmer() {
+    let kmer = Kmer::new(3);
+    assert_eq!(64, kmer.size());
+    assert_eq!(0, kmer.hash(b"AAA".to_vec()));
+    assert_eq!(0b01_10_11, kmer.hash(b"CGT".to_vec()));
+    assert_eq!(0b01_10_11, kmer.hash(b"cgt".to_vec()));
+    assert_eq!(b"TGA".to_vec(), kmer.invert(0b11_10_00));
+    for (i, k) in kmer.iter().enumerate() {
+        assert_eq!(i, kmer.hash(k));
+    }
+}
+
+#[test]
+fn test_kmer_counting() {
+    let kmer = Kmer::new(2);
+    let mut counts: Map<u32, Kmer> = Map::new(kmer);
+    let genome = b"ACGTACGA";
+    for window in genome.windows(2) {
+        counts[window.to_vec()] += 1;
+    }
+    assert_eq!(2, counts[b"AC".to_vec()]);
+    assert_eq!(1, counts[b"GA".to_vec()]);
+    assert_eq!(0, counts[b"AA".to_vec()]);
+    assert_eq!(7, counts.values().sum::<u32>());
+}
+
+#[test]
+#[should_panic]
+fn test_kmer_invalid() {
+    Kmer::new(2).hash(b"AN".to_vec());
+}
+
+#[test]
+fn test_codons() {
+    assert_eq!(64, Codons.size());
+    assert_eq!(0, Codons.hash(*b"AAA"));
+    assert_eq!(Codons.hash(*b"AUG"), Codons.hash(*b"atg"));
+    assert_eq!(Kmer::new(3).hash(b"ATG".to_vec()), Codons.hash(*b"ATG"));
+    assert_eq!(*b"TTT", Codons.invert(63));
+    for (i, k) in Codons.iter().enumerate() {
+        assert_eq!(i, Codons.hash(k));
+    }
+}
+
+#[test]
+fn test_amino_acids() {
+    let standard = AminoAcids::new();
+    assert_eq!(20, standard.size());
+    assert_eq!(0, standard.hash(b'A'));
+    assert_eq!(19, standard.hash(b'y'));
+    assert_eq!(b"ACDEFGHIKLMNPQRSTVWY".to_vec(), standard.iter().collect::<Vec<_>>());
+
+    let full = AminoAcids::new().with_stop().with_ambiguous();
+    assert_eq!(25, full.size());
+    assert_eq!(b"ACDEFGHIKLMNPQRSTVWY*BZJX".to_vec(), full.iter().collect::<Vec<_>>());
+    for (i, k) in full.iter().enumerate() {
+        assert_eq!(i, full.hash(k));
+    }
+    let ambiguous = AminoAcids::new().with_ambiguous();
+    assert_eq!(23, ambiguous.hash(b'X'));
+}
+
+#[test]
+#[should_panic]
+fn test_amino_acids_no_stop() {
+    AminoAcids::new().with_ambiguous().hash(b'*');
+}
+
+#[cfg(feature = "chrono")]
+#[test]
+fn test_calendar() {
+    use chrono::{Month, NaiveDate, Weekday};
+    use hashers::{DaysOfYear, Months, Weekdays};
+
+    assert_eq!(0, Weekdays.hash(Weekday::Mon));
+    assert_eq!(Weekday::Sun, Weekdays.invert(6));
+    assert_eq!(11, Months.hash(Month::December));
+    assert_eq!(Month::March, Months.invert(2));
+    for (i, k) in Weekdays.iter().enumerate() {
+        assert_eq!(i, Weekdays.hash(k));
+    }
+    for (i, k) in Months.iter().enumerate() {
+        assert_eq!(i, Months.hash(k));
+    }
+
+    assert_eq!(365, DaysOfYear::new(2023).size());
+    let days = DaysOfYear::new(2024);
+    assert_eq!(366, days.size());
+    let leap_day = NaiveDate::from_ymd_opt(2024, 2, 29).unwrap();
+    assert_eq!(59, days.hash(leap_day));
+    assert_eq!(leap_day, days.invert(59));
+    assert_eq!(NaiveDate::from_ymd_opt(2024, 12, 31).unwrap(), days.invert(365));
+}
+
+#[test]
+fn test_time_of_day() {
+    let minutes = TimeOfDay::minutes();
+    assert_eq!(1440, minutes.size());
+    assert_eq!(0, minutes.hash((0, 0)));
+    assert_eq!(1439, minutes.hash((23, 59)));
+    assert_eq!((13, 37), minutes.invert(minutes.hash((13, 37))));
+
+    let quarters = TimeOfDay::with_resolution(15);
+    assert_eq!(96, quarters.size());
+    assert_eq!(quarters.hash((13, 30)), quarters.hash((13, 44)));
+    assert_eq!((13, 30), quarters.invert(quarters.hash((13, 44))));
+    for (i, k) in quarters.iter().enumerate() {
+        assert_eq!(i, quarters.hash(k));
+    }
+}
+
+#[test]
+#[should_panic]
+fn test_time_of_day_bad_resolution() {
+    TimeOfDay::with_resolution(7);
+}
+
+#[test]
+fn test_char_range() {
+    let letters = CharRange::new('a'..='z');
+    assert_eq!(26, letters.size());
+    assert_eq!(0, letters.hash('a'));
+    assert_eq!(25, letters.hash('z'));
+    assert_eq!("abcdefghijklmnopqrstuvwxyz", letters.iter().collect::<String>());
+
+    let mut freq: Map<u64, _> = Map::new(letters);
+    for c in "hello".chars() {
+        freq[c] += 1;
+    }
+    assert_eq!(2, freq['l']);
+    assert_eq!(128, CharRange::ascii().size());
+}
+
+#[test]
+fn test_char_range_surrogates() {
+    let range = CharRange::new('\u{D7FE}'..='\u{E001}');
+    assert_eq!(4, range.size());
+    let actual = range.iter().collect::<Vec<_>>();
+    assert_eq!(vec!['\u{D7FE}', '\u{D7FF}', '\u{E000}', '\u{E001}'], actual);
+    for (i, k) in actual.into_iter().enumerate() {
+        assert_eq!(i, range.hash(k));
+    }
+    let above = CharRange::new('\u{E000}'..='\u{E00F}');
+    assert_eq!(16, above.size());
+    assert_eq!('\u{E00F}', above.invert(15));
+}
+
+#[test]
+#[should_panic]
+fn test_char_range_outside() {
+    CharRange::new('a'..='z').hash('A');
+}
+
+#[test]
+fn test_midi_notes() {
+    assert_eq!(128, MidiNotes.size());
+    assert_eq!(60, MidiNotes.hash((4, 0)));
+    assert_eq!(69, MidiNotes.hash((4, 9)));
+    assert_eq!((-1, 0), MidiNotes.invert(0));
+    assert_eq!((9, 7), MidiNotes.invert(127));
+    for (i, k) in MidiNotes.iter().enumerate() {
+        assert_eq!(i, MidiNotes.hash(k));
+    }
+}
+
+#[test]
+#[should_panic]
+fn test_midi_notes_out_of_range() {
+    MidiNotes.hash((9, 8));
+}
+
+#[test]
+fn test_ipv4_prefix() {
+    use std::net::Ipv4Addr;
+
+    let prefix = Ipv4Prefix::new(Ipv4Addr::new(192, 168, 17, 3), 16);
+    assert_eq!(Ipv4Addr::new(192, 168, 0, 0), prefix.network());
+    assert_eq!(65536, prefix.size());
+    assert_eq!(0x1103, prefix.hash(Ipv4Addr::new(192, 168, 17, 3)));
+    assert_eq!(Ipv4Addr::new(192, 168, 255, 255), prefix.invert(65535));
+    assert!(!prefix.contains(Ipv4Addr::new(192, 169, 0, 0)));
+
+    let mut seen = Set::new(Ipv4Prefix::new(Ipv4Addr::new(10, 0, 0, 0), 30));
+    seen.insert(Ipv4Addr::new(10, 0, 0, 2));
+    assert_eq!(vec![Ipv4Addr::new(10, 0, 0, 2)], seen.iter().collect::<Vec<_>>());
+    assert_eq!(1, Ipv4Prefix::new(Ipv4Addr::new(1, 2, 3, 4), 32).size());
+}
+
+#[test]
+#[should_panic]
+fn test_ipv4_prefix_outside() {
+    use std::net::Ipv4Addr;
+
+    Ipv4Prefix::new(Ipv4Addr::new(10, 0, 0, 0), 8).hash(Ipv4Addr::new(11, 0, 0, 0));
+}
+
+#[test]
+fn test_sudoku() {
+    let board = Sudoku::new(3);
+    assert_eq!(9, board.n());
+    assert_eq!(81, board.size());
+    assert_eq!(12, board.hash((1, 3)));
+    assert_eq!((8, 8), board.invert(80));
+    for (i, k) in board.iter().enumerate() {
+        assert_eq!(i, board.hash(k));
+    }
+    assert_eq!(0, board.box_of((2, 2)));
+    assert_eq!(5, board.box_of((4, 7)));
+    assert_eq!(8, board.box_of((8, 8)));
+}
+
+#[test]
+fn test_sudoku_peers() {
+    let board = Sudoku::new(3);
+    let peers = board.peers((4, 4));
+    assert_eq!(20, peers.len());
+    assert!(peers.contains(&(0, 4)));
+    assert!(peers.contains(&(4, 8)));
+    assert!(peers.contains(&(3, 5)));
+    assert!(!peers.contains(&(4, 4)));
+    assert!(!peers.contains(&(2, 2)));
+
+    let small = Sudoku::with_boxes(3, 2);
+    assert_eq!(36, small.size());
+    assert_eq!(3, small.box_of((2, 3)));
+    assert_eq!(vec![(0, 1), (0, 2), (0, 3), (0, 4), (0, 5),
+                    (1, 0), (1, 1), (1, 2),
+                    (2, 0), (3, 0), (4, 0), (5, 0)],
+               small.peers((0, 0)));
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Light {
+    Red,
+    Yellow,
+    Green,
+}
+
+impl Enumerable for Light {
+    const LENGTH: usize = 3;
+
+    fn from_usize(index: usize) -> Self {
+        match index {
+            0 => Light::Red,
+            1 => Light::Yellow,
+            2 => Light::Green,
+            _ => unreachable!(),
+        }
+    }
+
+    fn into_usize(self) -> usize {
+        self as usize
+    }
+}
+
+#[test]
+fn test_enum_hash() {
+    let lights = EnumHash::<Light>::new();
+    assert_eq!(3, lights.size());
+    assert_eq!(vec![Light::Red, Light::Yellow, Light::Green],
+               lights.iter().collect::<Vec<_>>());
+    let mut durations = Map::new(lights);
+    durations[Light::Green] = 30;
+    durations[Light::Red] = 40;
+    assert_eq!(vec![40, 0, 30], durations.values().cloned().collect::<Vec<_>>());
+}
+
+#[test]
+fn test_enum_hash_unbounded() {
+    // Light is neither `Default` nor `Hash`, which the hasher must not need.
+    let mymap = Map::<u8, EnumHash<Light>>::default();
+    assert_eq!(3, mymap.len());
+    assert_eq!(true, mymap == mymap.clone());
+
+    // No derives at all.
+    enum Bare {
+        Only,
+    }
+
+    impl Enumerable for Bare {
+        const LENGTH: usize = 1;
+
+        fn from_usize(_: usize) -> Self {
+            Bare::Only
+        }
+
+        fn into_usize(self) -> usize {
+            0
+        }
+    }
+
+    let bare = EnumHash::<Bare>::default();
+    assert_eq!(bare, bare.clone());
+    assert_eq!(true, format!("{:?}", bare).contains("Bare"));
+    let mut myset = Set::new(bare);
+    myset.insert(Bare::Only);
+    assert_eq!(true, myset.is_full());
+}
+
+#[cfg(feature = "strum")]
+#[test]
+fn test_enum_hash_strum() {
+    use strum::{EnumCount, FromRepr};
+
+    #[derive(Clone, Copy, Debug, PartialEq, EnumCount, FromRepr)]
+    enum Suit {
+        Spades,
+        Hearts,
+        Diamonds,
+        Clubs,
+    }
+
+    ::enumerable_from_strum!(Suit);
+
+    let suits = EnumHash::<Suit>::new();
+    assert_eq!(4, suits.size());
+    assert_eq!(2, suits.hash(Suit::Diamonds));
+    assert_eq!(Suit::Clubs, suits.invert(3));
+    assert_eq!(vec![Suit::Spades, Suit::Hearts, Suit::Diamonds, Suit::Clubs],
+               suits.iter().collect::<Vec<_>>());
+}
+
+#[cfg(feature = "enum-map")]
+#[test]
+fn test_enum_hash_enum_map() {
+    use enum_map::Enum;
+
+    #[derive(Clone, Copy, Debug, PartialEq, Enum)]
+    enum Direction {
+        North,
+        East,
+        South,
+        West,
+    }
+
+    let directions = EnumHash::<Direction>::new();
+    assert_eq!(4, directions.size());
+    assert_eq!(2, directions.hash(Direction::South));
+    assert_eq!(Direction::West, directions.invert(3));
+    assert_eq!(Direction::East, directions.invert(1));
+    assert_eq!(0, directions.hash(Direction::North));
+}
+
+#[test]
+fn test_shared_hasher() {
+    use std::rc::Rc;
+    use std::sync::Arc;
+
+    let pairs = Pairs::new(10);
+    let mut by_ref = Map::new(&pairs);
+    by_ref[(3, 4)] = 1;
+    assert_eq!(1, by_ref[(4, 3)]);
+
+    let shared = Rc::new(Pairs::new(10));
+    let mut mymap = Map::new(shared.clone());
+    let mut myset = Set::new(shared.clone());
+    mymap.insert((7, 2), 'x');
+    myset.insert((2, 7));
+    assert_eq!('x', mymap[(2, 7)]);
+    assert_eq!(true, myset.contains((7, 2)));
+    assert_eq!(3, Rc::strong_count(&shared));
+    assert_eq!((0, 1), shared.invert(1));
+
+    let boxed: Box<Pairs> = Box::new(Pairs::new(3));
+    assert_eq!(vec![(0, 0), (0, 1), (1, 1), (0, 2), (1, 2), (2, 2)],
+               boxed.iter().collect::<Vec<_>>());
+    assert_eq!(6, Map::<u8, _>::new(Arc::new(Pairs::new(3))).len());
+}
+
+#[test]
+fn test_dyn_hash() {
+    fn board(large: bool) -> DynHash<(usize, usize)> {
+        if large {
+            Box::new(OrderedPairs::new(4))
+        } else {
+            Box::new(Grid2::new(3, 2))
+        }
+    }
+
+    let mut small = Map::new(board(false));
+    let mut large = Map::new(board(true));
+    assert_eq!(6, small.len());
+    assert_eq!(16, large.len());
+    small[(2, 1)] = 5;
+    large[(2, 1)] = 7;
+    assert_eq!(vec![((2, 1), &5)],
+               small.iter().filter(|e| *e.1 != 0).collect::<Vec<_>>());
+    assert_eq!(vec![((2, 1), &7)],
+               large.iter().filter(|e| *e.1 != 0).collect::<Vec<_>>());
+    let hash: &dyn HashInverse<K = (usize, usize)> = &Grid2::new(3, 2);
+    assert_eq!((1, 1), hash.invert(4));
+}
+
+#[test]
+fn test_static_size() {
+    assert_eq!(Squares.size(), Squares::SIZE);
+    assert_eq!(Cards.size(), Cards::SIZE);
+    assert_eq!(768, <PieceSquares as StaticPerfectHash>::SIZE);
+    assert_eq!(3, <EnumHash<Light> as StaticPerfectHash>::SIZE);
+    assert_eq!(52 * 64, <(&Cards, Codons) as StaticPerfectHash>::SIZE);
+
+    let table = [0u8; <(Pieces, MidiNotes) as StaticPerfectHash>::SIZE];
+    assert_eq!((Pieces, MidiNotes).size(), table.len());
+}
+
+/* === Actual tests: Map === */
+
+#[test]
+fn test_map_basics() {
+    let mut mymap = Map::new(Pairs::new(10));
+    mymap.insert((3, 7), String::from("Hello"));
+    mymap[(7, 3)].push(' ');
+    mymap.insert((4, 3), String::from("lovely"));
+    mymap.insert((2, 9), String::from("World!"));
+    assert_eq!("Hello ", mymap.get((3, 7)));
+    assert_eq!("Hello ", mymap.get((7, 3)));
+    assert_eq!("", mymap.get((2, 2)));
+    assert_eq!("World!", mymap[(2, 9)]);
+    assert_eq!("World!", mymap[(9, 2)]);
+    assert_eq!("", mymap.get((7, 4)));
+    assert_eq!("", mymap.get((6, 6)));
+}
+
+#[test]
+fn test_map_swap() {
+    let mut mymap = Map::new(Pairs::new(10));
+
+    mymap.insert((3, 7), String::from("Hello"));
+    mymap.insert((4, 3), String::from("lovely"));
+    let mut world = String::from("World!");
+    assert_eq!("Hello", mymap[(3, 7)]);
+    assert_eq!("", mymap[(8, 9)]);
+    assert_eq!("lovely", mymap[(3, 4)]);
+    assert_eq!("World!", world);
+
+    mymap.swap((4, 3), &mut world);
+    assert_eq!("Hello", mymap[(3, 7)]);
+    assert_eq!("", mymap[(8, 9)]);
+    assert_eq!("World!", mymap[(3, 4)]);
+    assert_eq!("lovely", world);
+}
+
+#[test]
+fn test_map_iter() {
+    let mut mymap = Map::new(Pairs::new(3));
+    mymap.insert((0, 1), 42);
+    mymap.insert((1, 2), 123);
+    mymap.insert((1, 1), 0xCAFE);
+    mymap[(1, 0)] = 5;
+    let value_vec = mymap.values().copied().collect::<Vec<_>>();
+    assert_eq!(vec![0, 5, 0xCAFE, 0, 123, 0], value_vec);
+    let entry_vec = mymap.iter().map(|((a, b), &v)| (a, b, v))
+                         .collect::<Vec<_>>();
+    assert_eq!(vec![(0, 0, 0), (0, 1, 5),
+                    (1, 1, 0xCAFE), (0, 2, 0),
+                    (1, 2, 123), (2, 2, 0),], entry_vec);
+}
+
+#[test]
+fn test_map_clone() {
+    let mut mymap = Map::new(Pairs::new(10));
+    mymap.insert((3, 7), String::from("Hello"));
+    mymap[(7, 3)].push(' ');
+    mymap.insert((4, 3), String::from("lovely"));
+    mymap.insert((2, 9), String::from("World!"));
+    let othermap = mymap.clone();
+    assert_eq!("Hello ", othermap.get((3, 7)));
+    assert_eq!("Hello ", othermap.get((7, 3)));
+    assert_eq!("", othermap.get((2, 2)));
+    assert_eq!("World!", othermap[(2, 9)]);
+    assert_eq!("World!", othermap[(9, 2)]);
+    assert_eq!("", othermap.get((7, 4)));
+    assert_eq!("", othermap.get((6, 6)));
+}
+
+#[test]
+fn test_map_range() {
+    let mut mymap = Map::new(OrderedPairs::new(4));
+    for (i, (_, v)) in mymap.iter_mut().enumerate() {
+        *v = i;
+    }
+    let actual = mymap.range((1, 2)..(2, 1)).collect::<Vec<_>>();
+    assert_eq!(vec![((1, 2), &6), ((1, 3), &7), ((2, 0), &8)], actual);
+    assert_eq!(3, mymap.range((3, 1)..).count());
+    assert_eq!(2, mymap.range(..=(0, 1)).count());
+    assert_eq!(16, mymap.range(..).count());
+    assert_eq!(0, mymap.range((2, 2)..(2, 2)).count());
+    for (_, v) in mymap.range_mut((0, 0)..(1, 0)) {
+        *v = 100;
+    }
+    assert_eq!(vec![100, 100, 100, 100, 4],
+               mymap.values().take(5).cloned().collect::<Vec<_>>());
+}
+
+#[test]
+fn test_map_holes() {
+    let evens = Evens { n: 7 };
+    assert_eq!(vec![0, 2, 4, 6], evens.iter().collect::<Vec<_>>());
+    let mut mymap = Map::new(evens);
+    mymap[4] = 'x';
+    mymap[6] = 'y';
+    assert_eq!(vec![(0, &'\0'), (2, &'\0'), (4, &'x'), (6, &'y')],
+               mymap.iter().collect::<Vec<_>>());
+    for v in mymap.values_mut() {
+        *v = v.to_ascii_uppercase();
+    }
+    assert_eq!(vec!['\0', '\0', 'X', 'Y'],
+               mymap.values().cloned().collect::<Vec<_>>());
+    assert_eq!("['\\0', '\\0', 'X', 'Y']", format!("{:?}", mymap));
+    assert_eq!("{0: '\\0', 2: '\\0', 4: 'X', 6: 'Y'}", format!("{:?}", mymap.debug_entries()));
+
+    let tuple = (Evens { n: 3 }, Upto::new(2));
+    assert_eq!(vec![(0, 0), (0, 1), (2, 0), (2, 1)],
+               tuple.iter().collect::<Vec<_>>());
+}
+
+#[test]
+fn test_map_debug() {
+    let mut mymap = Map::new(Pairs::new(2));
+    mymap[(0, 1)] = 5;
+    assert_eq!("[0, 5, 0]", format!("{:?}", mymap));
+    assert_eq!("{(0, 0): 0, (0, 1): 5, (1, 1): 0}", format!("{:?}", mymap.debug_entries()));
+}
+
+#[test]
+fn test_map_get_ref() {
+    let mut mymap = Map::new(Permutations::new(3));
+    let key = vec![2, 0, 1];
+    *mymap.get_ref_mut(&key) += 3;
+    mymap[vec![0, 1, 2]] = 1;
+    assert_eq!(3, *mymap.get_ref(&key));
+    assert_eq!(1, *mymap.get_ref(&vec![0, 1, 2]));
+    assert_eq!(0, *mymap.get_ref(&vec![1, 0, 2]));
+    mymap[&key] *= 2;
+    assert_eq!(6, mymap[&key]);
+    assert_eq!(1, mymap[&vec![0, 1, 2]]);
+
+    let mut pairs = Map::new(Pairs::new(4));
+    pairs[(1, 3)] = 'x';
+    assert_eq!('x', *pairs.get_ref(&(3, 1)));
+
+    let mut letters = Map::new(CharRange::new('a'..='z'));
+    letters[&'q'] = 5;
+    assert_eq!(5, letters['q']);
+}
+
+#[test]
+fn test_hash_ref_consistent() {
+    use std::net::Ipv4Addr;
+
+    let comb = Combinations::new(7, 3);
+    for k in comb.iter() {
+        assert_eq!(comb.hash(k.clone()), comb.hash_ref(&k));
+        let mut reversed = k.clone();
+        reversed.reverse();
+        assert_eq!(comb.hash(k), comb.hash_ref(&reversed));
+    }
+    let multisets = BoundedMultisets::new(vec![2, 1, 3]);
+    for k in multisets.iter() {
+        assert_eq!(multisets.hash(k.clone()), multisets.hash_ref(&k));
+    }
+    let kmer = Kmer::new(4);
+    assert_eq!(kmer.hash(b"GATC".to_vec()), kmer.hash_ref(&b"gatc".to_vec()));
+    let chars = CharRange::new('\u{D000}'..='\u{E100}');
+    assert_eq!(chars.hash('\u{E001}'), chars.hash_ref(&'\u{E001}'));
+    let prefix = Ipv4Prefix::new(Ipv4Addr::new(10, 1, 0, 0), 16);
+    let addr = Ipv4Addr::new(10, 1, 2, 3);
+    assert_eq!(prefix.hash(addr), prefix.hash_ref(&addr));
+}
+
+/// Hashes one-letter `String`s, but can also look up plain `&str`s.
+struct Letters;
+
+impl PerfectHash for Letters {
+    type K = String;
+
+    fn hash(&self, k: String) -> usize {
+        self.hash_equivalent(&k[..])
+    }
+
+    fn size(&self) -> usize {
+        26
+    }
+}
+
+impl HashEquivalent<str> for Letters {
+    fn hash_equivalent(&self, k: &str) -> usize {
+        assert_eq!(1, k.len());
+        (k.as_bytes()[0] - b'a') as usize
+    }
+}
+
+#[test]
+fn test_map_get_by() {
+    let mut mymap = Map::new(Letters);
+    mymap["q".to_string()] = 17;
+    *mymap.get_by_mut("z") += 2;
+    assert_eq!(17, *mymap.get_by("q"));
+    assert_eq!(2, *mymap.get_by("z"));
+    assert_eq!(0, *mymap.get_by("a"));
+
+    let mut counts = Map::new(Kmer::new(2));
+    *counts.get_by_mut("AC") += 1;
+    *counts.get_by_mut(&b"ac"[..]) += 1;
+    assert_eq!(2, counts[b"AC".to_vec()]);
+
+    let perms = Permutations::new(3);
+    let shared = Map::new(&perms);
+    assert_eq!(0, *shared.get_by(&[2, 1, 0][..]));
+}
+
+#[test]
+fn test_map_replace() {
+    let mut mymap = Map::new(Pairs::new(4));
+    assert_eq!(0, mymap.replace((0, 2), 5));
+    assert_eq!(5, mymap.replace((2, 0), 6));
+    assert_eq!(6, mymap[(0, 2)]);
+}
+
+#[test]
+fn test_map_take() {
+    let mut mymap: Map<Vec<u8>, _> = Map::new(Pairs::new(4));
+    mymap[(1, 2)].push(42);
+    assert_eq!(vec![42], mymap.take((2, 1)));
+    assert!(mymap[(1, 2)].is_empty());
+}
+
+#[test]
+fn test_map_get_many_mut() {
+    let mut mymap = Map::new(Pairs::new(4));
+    mymap[(0, 1)] = 5;
+    {
+        let [a, b, c] = mymap.get_many_mut([(0, 1), (1, 2), (3, 0)]).unwrap();
+        *b += *a;
+        *c = 2 * *a;
+    }
+    assert_eq!(5, mymap[(2, 1)]);
+    assert_eq!(10, mymap[(0, 3)]);
+    assert!(mymap.get_many_mut([(0, 1), (1, 0)]).is_none());
+}
+
+#[test]
+fn test_map_swap_entries() {
+    let mut mymap = Map::new(Pairs::new(4));
+    mymap[(0, 1)] = "a".to_string();
+    mymap[(2, 3)] = "b".to_string();
+    mymap.swap_entries((1, 0), (2, 3));
+    assert_eq!("b", mymap[(0, 1)]);
+    assert_eq!("a", mymap[(3, 2)]);
+    mymap.swap_entries((0, 1), (1, 0));
+    assert_eq!("b", mymap[(0, 1)]);
+}
+
+#[test]
+fn test_map_fill_reset() {
+    let mut mymap = Map::new(Pairs::new(4));
+    mymap[(0, 1)] = 3;
+    mymap.fill(7);
+    assert_eq!(vec![7; 10], mymap.values().copied().collect::<Vec<_>>());
+    mymap.reset();
+    assert_eq!(vec![0; 10], mymap.values().copied().collect::<Vec<_>>());
+}
+
+#[test]
+fn test_map_reset_where() {
+    let mut mymap = Map::from_initial(Upto::new(6), vec![5, 1, 4, 1, 5, 9]);
+    mymap.reset_where(|k, &v| k % 2 == 0 || v > 4);
+    assert_eq!(vec![0, 1, 0, 1, 0, 0], mymap.values().copied().collect::<Vec<_>>());
+}
+
+#[test]
+fn test_map_map_values() {
+    let mymap = Map::from_initial(Upto::new(4), vec![0.2, 0.9, 0.5, 0.7]);
+    let quantized = mymap.map_values(|k, v| (v * 10.0) as u16 + k as u16,
+                                     |_| unreachable!());
+    assert_eq!(vec![2, 10, 7, 10], quantized.values().copied().collect::<Vec<_>>());
+
+    struct NoDefault(String);
+    let strings = quantized.map_values(|k, v| NoDefault(format!("{}:{}", k, v)),
+                                       |_| unreachable!());
+    assert_eq!("3:10", strings[3].0);
+}
+
+#[test]
+fn test_map_map_values_holes() {
+    let holey = Map::from_initial(Evens { n: 5 }, vec![1, 2, 3, 4, 5]);
+    let mapped = holey.map_values(|k, v| format!("{}:{}", k, v),
+                                  |v| format!("hole:{}", v));
+    assert_eq!(["0:1", "hole:2", "2:3", "hole:4", "4:5"], mapped.as_slice());
+}
+
+#[test]
+fn test_map_zip() {
+    let dist = Map::from_initial(Upto::new(3), vec![4, 0, 7]);
+    let heur = Map::from_initial(Upto::new(3), vec![1, 2, 3]);
+    let total = dist.zip_with(&heur, |d, h| d + h);
+    assert_eq!(vec![5, 2, 10], total.values().copied().collect::<Vec<_>>());
+
+    let mut flags: Map<bool, _> = Map::new(Upto::new(3));
+    flags.zip_assign(&dist, |f, &d| *f = d > 3);
+    assert_eq!(vec![true, false, true], flags.values().copied().collect::<Vec<_>>());
+}
+
+#[test]
+#[should_panic]
+fn test_map_zip_mismatch() {
+    let small: Map<u8, _> = Map::new(Upto::new(3));
+    let large: Map<u8, _> = Map::new(Upto::new(4));
+    small.zip_with(&large, |a, b| a + b);
+}
+
+#[test]
+fn test_map_merge_from() {
+    let mut total: Map<u32, _> = Map::new(Pairs::new(3));
+    for shard in 0..3 {
+        let mut partial = Map::new(Pairs::new(3));
+        partial[(shard, 2)] = 1;
+        partial[(0, 0)] = 10;
+        total.merge_from(&partial, |dst, src| *dst += src);
+    }
+    assert_eq!(30, total[(0, 0)]);
+    assert_eq!(1, total[(2, 0)]);
+    assert_eq!(1, total[(2, 2)]);
+    assert_eq!(0, total[(1, 0)]);
+}
+
+#[test]
+fn test_map_diff() {
+    let before = Map::from_initial(Upto::new(5), vec![1, 2, 3, 4, 5]);
+    let mut after = before.clone();
+    after[1] = 7;
+    after[4] = 0;
+    assert_eq!(vec![(1, &2, &7), (4, &5, &0)], before.diff(&after).collect::<Vec<_>>());
+    assert_eq!(0, after.diff(&after).count());
+}
+
+#[test]
+fn test_map_from_pairs() {
+    let mymap = Map::from_pairs(Pairs::new(3), vec![((0, 1), 'a'), ((2, 2), 'b'), ((1, 0), 'c')]);
+    assert_eq!('c', mymap[(0, 1)]);
+    assert_eq!('b', mymap[(2, 2)]);
+    assert_eq!('\0', mymap[(0, 2)]);
+
+    let mut mymap = mymap;
+    mymap.extend((0..3).map(|i| ((i, i), 'd')));
+    assert_eq!('d', mymap[(2, 2)]);
+    assert_eq!('c', mymap[(1, 0)]);
+
+    let board: Map<u8, Squares> = (0..8).map(|f| (Square::new(f, 1), 1)).collect();
+    assert_eq!(8, board.values().filter(|&&v| v == 1).count());
+}
+
+#[test]
+fn test_map_keys() {
+    let mymap: Map<u8, _> = Map::new(Evens { n: 7 });
+    assert_eq!(vec![0, 2, 4, 6], mymap.keys().collect::<Vec<_>>());
+    let mymap: Map<u8, _> = Map::new(Pairs::new(2));
+    assert_eq!(mymap.iter().map(|(k, _)| k).collect::<Vec<_>>(), mymap.keys().collect::<Vec<_>>());
+}
+
+#[test]
+fn test_map_try_from_initial() {
+    let mymap = Map::try_from_initial(Upto::new(3), vec![4, 5, 6]).unwrap();
+    assert_eq!(5, mymap[1]);
+    let err = Map::try_from_initial(Upto::new(3), vec![4, 5]).unwrap_err();
+    assert_eq!(WrongLength { expected: 3, actual: 2 }, err);
+    assert_eq!("expected 3 values, got 2", err.to_string());
+}
+
+#[test]
+fn test_map_zeroed_construction() {
+    let zeros = Map::from_element(Upto::new(1000), &0u64);
+    assert_eq!(0, zeros[999]);
+    let empty: Map<u8, _> = Map::from_element(Upto::new(0), &1);
+    assert_eq!(true, empty.is_empty());
+
+    let zeroed: Map<u64, _> = Map::new_zeroed(Upto::new(1000));
+    assert_eq!(zeros.as_slice(), zeroed.as_slice());
+    let flags: Map<bool, _> = Map::new_zeroed(Pairs::new(4));
+    assert_eq!(Map::new(Pairs::new(4)), flags);
+    let floats: Map<f64, _> = Map::new_zeroed(Upto::new(3));
+    assert_eq!(&[0.0; 3], floats.as_slice());
+    let empty: Map<char, _> = Map::new_zeroed(Upto::new(0));
+    assert_eq!(true, empty.is_empty());
+}
+
+#[test]
+fn test_map_update() {
+    let mut mymap = Map::new(Pairs::new(5));
+    mymap.update((1, 3), |v| *v += 4);
+    mymap.update((3, 1), |v| *v *= 3);
+    assert_eq!(12, mymap[(1, 3)]);
+}
+
+#[test]
+fn test_map_entry() {
+    let mut mymap: Map<u32, _> = Map::new(Pairs::new(5));
+    assert_eq!(7, mymap.entry((2, 4)).and_modify(|v| *v += 7).replace(3));
+    assert_eq!(3, *mymap.entry((4, 2)).get());
+    *mymap.entry((4, 2)).into_mut() += 1;
+    assert_eq!(8, mymap.entry((2, 4)).and_modify(|v| *v *= 2).take());
+    assert_eq!(0, mymap[(2, 4)]);
+}
+
+#[test]
+fn test_map_view() {
+    let buffer = [3u8, 1, 4, 1, 5, 9, 2, 6, 5, 3];
+    let view = MapView::from_storage(Pairs::new(4), &buffer[..]);
+    assert_eq!(10, view.len());
+    assert_eq!(buffer[Pairs::new(4).hash((3, 1))], view[(1, 3)]);
+    assert_eq!(buffer.iter().sum::<u8>(), view.values().sum());
+    assert_eq!(view.iter().count(), 10);
+
+    let mut buffer = vec![0u32; 5];
+    {
+        let mut view = MapViewMut::from_storage(Evens { n: 5 }, &mut buffer[..]);
+        view[2] = 7;
+        *view.get_mut(4) += 1;
+        for (k, v) in view.iter_mut() {
+            *v += k as u32;
+        }
+        assert_eq!(vec![0, 9, 5], view.values().copied().collect::<Vec<_>>());
+    }
+    assert_eq!(vec![0, 0, 9, 0, 5], buffer);
+}
+
+#[test]
+fn test_map_storage() {
+    let mut vecmap = Map::from_storage(Upto::new(3), vec![1, 2, 3]);
+    vecmap[2] = 5;
+    assert_eq!(vec![1, 2, 5], vecmap.values().copied().collect::<Vec<_>>());
+
+    let mut arraymap = Map::from_storage(Squares, [false; 64]);
+    arraymap[Square::new(3, 3)] = true;
+    assert_eq!(1, arraymap.values().filter(|&&b| b).count());
+
+    let mut boxed = Map::from_initial(Upto::new(3), vec![0, 0, 0]);
+    boxed.merge_from(&vecmap, |dst, src| *dst += src);
+    assert_eq!(0, boxed.diff(&vecmap).count());
+}
+
+#[cfg(feature = "allocator_api")]
+#[test]
+fn test_new_in() {
+    use std::alloc::{AllocError, Allocator, Global, Layout};
+    use std::cell::Cell;
+    use std::ptr::NonNull;
+
+    struct Counting<'a>(&'a Cell<usize>);
+
+    unsafe impl<'a> Allocator for Counting<'a> {
+        fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+            self.0.set(self.0.get() + 1);
+            Global.allocate(layout)
+        }
+
+        unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+            Global.deallocate(ptr, layout)
+        }
+    }
+
+    let count = Cell::new(0);
+    let mut mymap = Map::new_in(Pairs::new(4), Counting(&count));
+    mymap[(3, 1)] = 7u8;
+    assert_eq!(1, count.get());
+    assert_eq!(vec![7], mymap.values().copied().filter(|&v| v != 0)
+                             .collect::<Vec<_>>());
+
+    let mut myset = Set::new_in(Pairs::new(4), Counting(&count));
+    myset.insert((1, 3));
+    assert_eq!(2, count.get());
+    assert_eq!(vec![(1, 3)], myset.iter().collect::<Vec<_>>());
+}
+
+#[test]
+fn test_map_slices() {
+    let mut mymap = Map::new(Pairs::new(3));
+    mymap[(1, 2)] = 4.0;
+    mymap.as_mut_slice().iter_mut().for_each(|v| *v += 0.5);
+    assert_eq!(6, mymap.as_slice().len());
+    assert_eq!(7.0, mymap.as_slice().iter().sum::<f64>());
+    let raw = mymap.into_boxed_slice();
+    assert_eq!(4.5, raw[Pairs::new(3).hash((2, 1))]);
+}
+
+#[test]
+fn test_map_parts() {
+    let mut mymap = Map::new(Pairs::new(3));
+    mymap[(0, 2)] = 'x';
+    let (hash, backing) = mymap.into_parts();
+    let mymap = Map::from_storage(hash, backing);
+    assert_eq!('x', mymap[(2, 0)]);
+
+    let (hash, backing) = mymap.into_parts();
+    let mymap = Map::try_from_initial(hash, backing.into_vec()).unwrap();
+    assert_eq!('x', mymap[(0, 2)]);
+}
+
+#[test]
+fn test_map_hasher() {
+    let mymap: Map<u8, _> = Map::new(Pairs::new(5));
+    assert_eq!(15, mymap.hasher().size());
+    assert_eq!((2, 3), mymap.hasher().invert(mymap.hasher().hash((3, 2))));
+
+    let myset = Set::new(Squares);
+    assert_eq!(64, myset.hasher().size());
+}
+
+#[test]
+fn test_map_leak() {
+    let table: &'static mut [u64] = Map::from_fn(Upto::new(10), |k| 1 << k).leak();
+    assert_eq!(512, table[9]);
+
+    let mut leaked: LeakedMap<u64, _> = Map::from_fn(Pairs::new(3), |(a, b)| (a * b) as u64).leak_map();
+    leaked[(2, 1)] += 1;
+    assert_eq!(3, leaked[(1, 2)]);
+    let values: &'static [u64] = leaked.into_parts().1;
+    assert_eq!(6, values.len());
+}
+
+#[test]
+fn test_map_default() {
+    #[derive(Default)]
+    struct Board {
+        counts: Map<u8, Squares>,
+    }
+
+    let mut board = Board::default();
+    board.counts[Square::new(4, 4)] += 1;
+    assert_eq!(64, board.counts.len());
+    assert_eq!(1, board.counts.values().map(|&v| v as u32).sum::<u32>());
+}
+
+#[test]
+fn test_map_eq_hash() {
+    use std::collections::HashSet;
+
+    let mut a = Map::new(Pairs::new(3));
+    let mut b = Map::new(Pairs::new(3));
+    a[(0, 1)] = 2;
+    b[(1, 0)] = 2;
+    assert_eq!(a, b);
+    b[(2, 2)] = 1;
+    assert_ne!(a, b);
+    assert_ne!(Map::<u8, _>::new(Pairs::new(3)), Map::new(Pairs::new(2)));
+
+    let mut seen = HashSet::new();
+    assert!(seen.insert(a.clone()));
+    assert!(seen.insert(b));
+    assert!(!seen.insert(a));
+}
+
+#[test]
+fn test_map_iter_range() {
+    let mymap = Map::from_initial(Evens { n: 9 }, (10..19).collect());
+    assert_eq!(vec![(4, &14), (6, &16)], mymap.iter_range(3..7).collect::<Vec<_>>());
+    assert_eq!(vec![&16, &18], mymap.values_range(5..).collect::<Vec<_>>());
+    assert_eq!(vec![&10, &12], mymap.values_range(..=2).collect::<Vec<_>>());
+    assert_eq!(0, mymap.iter_range(7..100).filter(|e| e.0 > 8).count());
+
+    let halves = [mymap.values_range(..4).sum::<u32>(), mymap.values_range(4..).sum()];
+    assert_eq!(mymap.values().sum::<u32>(), halves[0] + halves[1]);
+}
+
+#[test]
+fn test_map_cursor_mut() {
+    let mut mymap = Map::from_initial(Evens { n: 7 }, vec![1, 0, 2, 0, 3, 0, 4]);
+    {
+        let mut cursor = mymap.cursor_mut();
+        assert_eq!(Some(0), cursor.key());
+        assert_eq!(None, cursor.peek_prev());
+        cursor.move_next();
+        assert_eq!(Some(2), cursor.key());
+        assert_eq!(Some((4, &3)), cursor.peek_next());
+        *cursor.value_mut().unwrap() *= 10;
+        cursor.swap_with(6);
+        cursor.move_next();
+        cursor.move_next();
+        cursor.move_next();
+        assert_eq!(None, cursor.key());
+        assert_eq!(None, cursor.value());
+        assert_eq!(Some((0, &1)), cursor.peek_next());
+        cursor.move_prev();
+        assert_eq!(Some(6), cursor.key());
+        assert_eq!(Some(&20), cursor.value());
+    }
+    assert_eq!(vec![1, 4, 3, 20], mymap.values().copied().collect::<Vec<_>>());
+
+    let mut empty: Map<u8, _> = Map::new(Upto::new(0));
+    let mut cursor = empty.cursor_mut();
+    cursor.move_next();
+    cursor.move_prev();
+    assert_eq!(None, cursor.key());
+}
+
+#[test]
+fn test_map_chunks() {
+    let mut mymap = Map::from_initial(Evens { n: 7 }, (0..7).collect());
+    let sizes = mymap.chunks(3).map(|c| c.as_slice().len()).collect::<Vec<_>>();
+    assert_eq!(vec![3, 3, 1], sizes);
+    let keys = mymap.chunks(3)
+        .map(|c| c.map(|(k, _)| k).collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    assert_eq!(vec![vec![0, 2], vec![4], vec![6]], keys);
+
+    std::thread::scope(|scope| {
+        for chunk in mymap.chunks_mut(2) {
+            scope.spawn(move || {
+                for (k, v) in chunk {
+                    *v += k * 10;
+                }
+            });
+        }
+    });
+    assert_eq!(vec![0, 22, 44, 66], mymap.values().copied().collect::<Vec<_>>());
+    let tail = mymap.chunks_mut(4).nth(1).unwrap().into_slice();
+    assert_eq!(&[44, 5, 66][..], tail);
+}
 
-/* === Example use case === */
+#[test]
+fn test_map_for_each_pair_mut() {
+    let mut mymap = Map::from_initial(Evens { n: 7 }, vec![0; 7]);
+    let mut pairs = Vec::new();
+    mymap.for_each_pair_mut(|a, va, b, vb| {
+        pairs.push((a, b));
+        *va += 1;
+        *vb += 10;
+    });
+    assert_eq!(vec![(0, 2), (0, 4), (0, 6), (2, 4), (2, 6), (4, 6)], pairs);
+    assert_eq!(vec![3, 12, 21, 30], mymap.values().copied().collect::<Vec<_>>());
+}
 
-#[derive(Clone)]
-struct Pairs {
-    n: usize,
+#[test]
+fn test_map_batch() {
+    let mut mymap: Map<usize, _> = Map::new(Pairs::new(4));
+    mymap.for_each_batch_mut(&[(0, 1), (3, 2), (1, 0)], |&(a, b), v| *v += a + b);
+    assert_eq!(vec![&2, &5, &0], mymap.get_batch(&[(1, 0), (2, 3), (3, 3)]));
+    assert!(mymap.get_batch(&[]).is_empty());
 }
 
-impl Pairs {
-    pub fn new(n: usize) -> Self {
-        Pairs { n: n }
+#[test]
+fn test_map_sorted() {
+    let heat = Map::from_initial(Evens { n: 9 }, vec![5, 0, 9, 0, 1, 0, 9, 0, 4]);
+    assert_eq!(vec![4, 8, 0, 2, 6],
+               heat.iter_sorted_by(|a, b| a.cmp(b)).map(|(k, _)| k).collect::<Vec<_>>());
+    assert_eq!(5, heat.iter_sorted_by(|a, b| a.cmp(b)).len());
+    let hottest = heat.top_k(3, |a, b| a.cmp(b));
+    assert_eq!(3, hottest.len());
+    assert_eq!(vec![&9, &9, &5], hottest.iter().map(|e| e.1).collect::<Vec<_>>());
+    assert_eq!(5, heat.top_k(10, |a, b| a.cmp(b)).len());
+    assert!(heat.top_k(0, |a, b| a.cmp(b)).is_empty());
+}
+
+#[test]
+fn test_map_extremes() {
+    let heat = Map::from_initial(Evens { n: 9 }, vec![5, 99, 9, -99, 1, 0, 9, 0, 4]);
+    assert_eq!(Some((6, &9)), heat.max_by_value());
+    assert_eq!(Some((4, &1)), heat.min_by_value());
+    assert_eq!(Some((0, &5)), heat.min_by_value_key(|&v| (v - 5i32).abs()));
+    assert_eq!(Some((6, &9)), heat.max_by_value_key(|&v| (v - 5i32).abs()));
+
+    let empty: Map<u8, _> = Map::new(Upto::new(0));
+    assert_eq!(None, empty.max_by_value());
+}
+
+#[test]
+fn test_map_argsort() {
+    let scores = Map::from_initial(Upto::new(5), vec![0.5, 0.1, 0.9, 0.1, 0.3]);
+    let ranking = scores.argsort_by(|a, b| a.partial_cmp(b).unwrap());
+    assert_eq!(vec![1, 3, 4, 0, 2], ranking);
+    let median = ranking[ranking.len() / 2];
+    assert_eq!(0.3, scores[median]);
+}
+
+#[test]
+fn test_map_arithmetic() {
+    let a = Map::from_initial(Pairs::new(2), vec![1.0, 2.0, 3.0]);
+    let b = Map::from_initial(Pairs::new(2), vec![0.5, 0.5, 1.0]);
+    let mut sum = a.clone() + b.clone();
+    assert_eq!(&[1.5, 2.5, 4.0][..], sum.as_slice());
+    sum -= &a;
+    assert_eq!(sum, b);
+    sum += &b;
+    sum.scale(2.0);
+    assert_eq!(&[2.0, 2.0, 4.0][..], sum.as_slice());
+    assert_eq!(&[0.0, 0.0, 0.0][..], (sum.clone() - sum).as_slice());
+}
+
+#[test]
+fn test_map_counter() {
+    let mut counts: Map<u8, _> = Map::new(Kmer::new(1));
+    for base in b"GATTACA".windows(1) {
+        counts.increment(base.to_vec());
     }
+    assert_eq!(3, counts[b"A".to_vec()]);
+    assert_eq!(5, counts.add_count(b"T".to_vec(), 3));
+    assert_eq!(255, counts.saturating_add(b"T".to_vec(), 254));
+
+    let mut balance: Map<i64, _> = Map::new(Upto::new(2));
+    assert_eq!(-4, balance.add_count(1, -4));
+    assert_eq!(1, balance.increment(0));
+}
+
+#[test]
+fn test_map_apply() {
+    let mut mymap = Map::new(Evens { n: 7 });
+    mymap.apply(|k, v| *v = k * k);
+    assert_eq!(vec![0, 4, 16, 36], mymap.values().copied().collect::<Vec<_>>());
+}
+
+#[test]
+fn test_map_find_count() {
+    let mymap = Map::from_initial(Evens { n: 9 }, vec![5, 9, 9, 9, 1, 9, 9, 9, 4]);
+    assert_eq!(Some(2), mymap.find_key(|_, &v| v > 5));
+    assert_eq!(Some(4), mymap.find_key(|k, &v| k > 0 && v < 5));
+    assert_eq!(None, mymap.find_key(|_, &v| v > 9));
+    assert_eq!(3, mymap.count_where(|_, &v| v != 9));
+    assert_eq!(2, mymap.count_where(|k, _| k > 4));
+}
+
+#[test]
+fn test_map_fold_with_keys() {
+    let mut mass = Map::new(Grid2::new(3, 2));
+    mass[(2, 0)] = 1.0;
+    mass[(0, 1)] = 3.0;
+    let (total, x, y) = mass.fold_with_keys((0.0, 0.0, 0.0), |(m, x, y), (cx, cy), &w| {
+        (m + w, x + w * cx as f64, y + w * cy as f64)
+    });
+    assert_eq!(4.0, total);
+    assert_eq!((0.5, 0.75), (x / total, y / total));
+}
 
-    fn sort((u, v): (usize, usize)) -> (usize, usize) {
-        if u > v {
-            (v, u)
+#[test]
+fn test_map_non_default() {
+    let mut counts: Map<u32, _> = Map::new(Pairs::new(100));
+    counts[(3, 4)] = 2;
+    counts[(50, 7)] = 1;
+    assert_eq!(2, counts.count_non_default());
+    assert_eq!(vec![((3, 4), &2), ((7, 50), &1)],
+               counts.iter_non_default().map(|((a, b), v)| ((a.min(b), a.max(b)), v)).collect::<Vec<_>>());
+}
+
+#[test]
+fn test_map_invert_values() {
+    let labels = Map::from_initial(Grid2::new(3, 2), vec!['a', 'b', 'a', 'c', 'a', 'b']);
+    let cells = labels.invert_values();
+    assert_eq!(3, cells.len());
+    assert_eq!(vec![(0, 0), (2, 0), (1, 1)], cells[&'a']);
+    assert_eq!(vec![(0, 1)], cells[&'c']);
+}
+
+#[test]
+fn test_map_group_by_value() {
+    let clusters = Map::from_initial(Upto::new(6), vec![2.5, 1.0, 2.5, 2.5, 7.0, 1.0]);
+    let groups = clusters.group_by_value()
+        .map(|(&v, keys)| (v, keys.collect::<Vec<_>>()))
+        .collect::<Vec<_>>();
+    assert_eq!(vec![(2.5, vec![0, 2, 3]), (1.0, vec![1, 5]), (7.0, vec![4])], groups);
+}
+
+#[test]
+fn test_map_rehash() {
+    let mut quick = Map::new(Grid2::new(4, 4));
+    quick[(3, 1)] = 'x';
+    quick[(0, 2)] = 'y';
+    let morton = quick.clone().rehash(ZOrder::new(Grid2::new(4, 4)), |k| k);
+    assert_eq!('x', morton[(3, 1)]);
+    assert_eq!('y', morton[(0, 2)]);
+    assert_eq!(2, morton.count_non_default());
+
+    let wide = quick.rehash(Grid2::new(5, 4), |(x, y)| (x + 1, y));
+    assert_eq!('x', wide[(4, 1)]);
+    assert_eq!('\0', wide[(3, 1)]);
+}
+
+#[test]
+#[should_panic]
+fn test_map_rehash_collision() {
+    Map::<u8, _>::new(Upto::new(3)).rehash(Upto::new(3), |_| 0);
+}
+
+#[test]
+fn test_map_grow_into() {
+    let small = Map::from_fn(Grid2::new(2, 2), |(x, y)| x + 10 * y);
+    let big = small.grow_into(Grid2::new(3, 4), 99);
+    assert_eq!(11, big[(1, 1)]);
+    assert_eq!(10, big[(0, 1)]);
+    assert_eq!(99, big[(2, 1)]);
+    assert_eq!(99, big[(0, 3)]);
+    assert_eq!(8, big.count_where(|_, &v| v == 99));
+}
+
+#[test]
+fn test_map_restrict_into() {
+    let big = Map::from_fn(Grid2::new(4, 4), |(x, y)| x + 10 * y);
+    let cropped = big.restrict_into(Grid2::new(2, 2), |(x, y)| {
+        if (1..3).contains(&x) && (1..3).contains(&y) {
+            Some((x - 1, y - 1))
         } else {
-            (u, v)
+            None
         }
-    }
+    });
+    assert_eq!(vec![11, 12, 21, 22], cropped.values().copied().collect::<Vec<_>>());
+}
 
-    fn size_when(n: usize) -> usize {
-        (n + 1) * n / 2
+#[test]
+fn test_map_split_partitions() {
+    let mut grid: Map<usize, _> = Map::new(Grid2::new(4, 3));
+    {
+        let mut parts = grid.split_partitions(2, |&(x, _)| x / 2);
+        assert_eq!(6, parts[0].len());
+        assert_eq!(None, parts[0].get((3, 0)));
+        *parts[1].get_mut((3, 0)).unwrap() = 7;
+        std::thread::scope(|scope| {
+            for (n, mut part) in parts.into_iter().enumerate() {
+                scope.spawn(move || {
+                    for (_, v) in part.iter_mut() {
+                        *v += n + 1;
+                    }
+                });
+            }
+        });
     }
+    assert_eq!(vec![1, 1, 2, 9], (0..4).map(|x| grid[(x, 0)]).collect::<Vec<_>>());
 }
 
-impl PerfectHash for Pairs {
-    type K = (usize, usize);
+#[test]
+fn test_map_sub_view() {
+    fn boundary(&(x, y): &(usize, usize)) -> bool {
+        x == 0 || y == 0 || x == 3 || y == 3
+    }
 
-    fn hash(&self, k: Self::K) -> usize {
-        let (a, b) = Self::sort(k);
-        a + Self::size_when(b)
+    fn sum_boundary<P: Fn(&(usize, usize)) -> bool>(view: SubMapView<u32, Grid2, P>) -> u32 {
+        view.iter().map(|(_, &v)| v).sum()
     }
 
-    fn size(&self) -> usize {
-        Self::size_when(self.n)
+    let mut grid = Map::from_fn(Grid2::new(4, 4), |(x, y)| (x + y) as u32);
+    {
+        let mut inner = grid.sub_view_mut(|k| !boundary(k));
+        assert_eq!(None, inner.get_mut((0, 2)));
+        for (_, v) in inner.iter_mut() {
+            *v = 0;
+        }
+        assert_eq!(4, inner.iter().count());
     }
+    assert_eq!(Some(&0), grid.sub_view(|k| !boundary(k)).get((2, 1)));
+    assert_eq!(None, grid.sub_view(boundary).get((2, 1)));
+    assert_eq!(true, grid.sub_view(boundary).contains_key(&(3, 1)));
+    assert_eq!(36, sum_boundary(grid.sub_view(boundary)));
 }
 
-impl HashInverse for Pairs {
-    fn invert(&self, index: usize) -> Self::K {
-        assert!(index < self.size());
-        /* Count down from n-1 to 0. */
-        for sub in 0..self.n {
-            let use_b = self.n - 1 - sub;
-
-            let offset = Self::size_when(use_b);
-            if offset <= index {
-                /* Found the largest 'use_b' that results
-                   in a compatible offset */
-                let use_a = index - offset;
-                assert!(use_a <= use_b);
-                return (use_a, use_b);
-            }
-        }
-        panic!("Unsigned integer {} appears to be strictly smaller than zero 0.",
-               index);
+#[test]
+fn test_masked_map() {
+    let mut mymap = MaskedMap::new(Pairs::new(5));
+    assert_eq!(true, mymap.is_empty());
+    assert_eq!(None, mymap.insert((1, 3), "a"));
+    assert_eq!(None, mymap.insert((4, 0), "b"));
+    assert_eq!(Some("a"), mymap.insert((3, 1), "c"));
+    assert_eq!(2, mymap.len());
+    assert_eq!(Some(&"c"), mymap.get((1, 3)));
+    assert_eq!(None, mymap.get((2, 2)));
+    assert_eq!(false, mymap.contains_key((2, 2)));
+    *mymap.get_mut((0, 4)).unwrap() = "d";
+    assert_eq!(vec![((1, 3), &"c"), ((0, 4), &"d")],
+               mymap.iter().map(|((a, b), v)| ((a.min(b), a.max(b)), v)).collect::<Vec<_>>());
+    assert_eq!(Some("c"), mymap.remove((1, 3)));
+    assert_eq!(None, mymap.remove((1, 3)));
+    assert_eq!(1, mymap.len());
+    assert_eq!(true, mymap.mask().contains((4, 0)));
+}
+
+#[test]
+fn test_masked_map_mask_len() {
+    let mut mymap = MaskedMap::new(Grid2::new(2, 2));
+    mymap.insert((0, 0), 'a');
+    mymap.insert((1, 1), 'b');
+    assert_eq!(2, mymap.mask().len());
+    assert_eq!(Some('b'), mymap.remove((1, 1)));
+    assert_eq!(1, mymap.mask().len());
+    assert_eq!(1, mymap.len());
+    assert_eq!(false, mymap.mask().clone().erase((1, 1)));
+    mymap.insert((0, 1), 'c');
+    mymap.insert((1, 0), 'd');
+    mymap.insert((1, 1), 'e');
+    assert_eq!(true, mymap.mask().is_full());
+    assert_eq!(Some('a'), mymap.remove((0, 0)));
+    assert_eq!(false, mymap.mask().is_full());
+    assert_eq!(3, mymap.mask().len());
+}
+
+#[test]
+fn test_map_get_unchecked() {
+    let mut mymap = Map::new(Squares);
+    let sq = Square::new(6, 2);
+    unsafe {
+        *mymap.get_unchecked_mut(sq) += 3;
+        assert_eq!(3, *mymap.get_unchecked(sq));
     }
+    assert_eq!(3, mymap[sq]);
 }
 
-/* === Actual tests: default implementations === */
+#[test]
+fn test_map_by_hash() {
+    let grid = Grid2::new(5, 5);
+    let mut life: Map<u8, _> = Map::new(grid);
+    let neighbors = grid.iter()
+        .map(|k| grid.neighbors8(k).map(|n| life.index_of(n)).collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    *life.get_by_hash_mut(life.index_of((2, 2))) = 1;
+    let center = life.index_of((2, 1));
+    assert_eq!(1, neighbors[center].iter().map(|&i| *life.get_by_hash(i)).sum::<u8>());
+}
 
 #[test]
-fn test_domain_iter() {
-    let pairs = Pairs::new(4);
-    let actual = pairs.iter().collect::<Vec<_>>();
-    let expected = vec![(0, 0),
-                        (0, 1), (1, 1),
-                        (0, 2), (1, 2), (2, 2),
-                        (0, 3), (1, 3), (2, 3), (3, 3)];
-    assert_eq!(actual, expected);
+fn test_map_memory_usage() {
+    let map: Map<u64, _> = Map::new(Upto::new(10));
+    assert_eq!(80, map.memory_usage());
+    let map: Map<(), _> = Map::new(Upto::new(10));
+    assert_eq!(0, map.memory_usage());
 }
 
-/* === Actual tests: Map === */
+#[test]
+fn test_map_transpose() {
+    let map = Map::from_fn((Upto::new(2), Upto::new(3)), |(i, j)| i * 10 + j);
+    assert_eq!(&[0, 1, 2, 10, 11, 12], map.as_slice());
+    let map = map.transpose();
+    assert_eq!(&[0, 10, 1, 11, 2, 12], map.as_slice());
+    assert_eq!(12, map[(2, 1)]);
+    let map = map.transpose();
+    assert_eq!(&[0, 1, 2, 10, 11, 12], map.as_slice());
+}
 
 #[test]
-fn test_map_basics() {
-    let mut mymap = Map::new(Pairs::new(10));
-    mymap.insert((3, 7), String::from("Hello"));
-    mymap[(7, 3)].push(' ');
-    mymap.insert((4, 3), String::from("lovely"));
-    mymap.insert((2, 9), String::from("World!"));
-    assert_eq!("Hello ", mymap.get((3, 7)));
-    assert_eq!("Hello ", mymap.get((7, 3)));
-    assert_eq!("", mymap.get((2, 2)));
-    assert_eq!("World!", mymap[(2, 9)]);
-    assert_eq!("World!", mymap[(9, 2)]);
-    assert_eq!("", mymap.get((7, 4)));
-    assert_eq!("", mymap.get((6, 6)));
+fn test_map_broadcast() {
+    let base = Map::from_fn(Upto::new(2), |i| i + 5);
+    let map = base.broadcast(Upto::new(3));
+    assert_eq!(&[5, 5, 5, 6, 6, 6], map.as_slice());
+    assert_eq!(6, map[(1, 2)]);
+    let empty = Map::from_fn(Upto::new(2), |i| i).broadcast(Upto::new(0));
+    assert!(empty.is_empty());
 }
 
 #[test]
-fn test_map_swap() {
-    let mut mymap = Map::new(Pairs::new(10));
+fn test_map_reduce() {
+    let map = Map::from_fn((Upto::new(2), Upto::new(3)), |(i, j)| i * 10 + j);
+    let rows = map.reduce_rows(0, |acc, _, &v| acc + v);
+    assert_eq!(&[3, 33], rows.as_slice());
+    let cols = map.reduce_cols(Vec::new(), |mut acc, i, &v| {
+        acc.push((i, v));
+        acc
+    });
+    assert_eq!(&[(0, 2), (1, 12)], &cols[2][..]);
 
-    mymap.insert((3, 7), String::from("Hello"));
-    mymap.insert((4, 3), String::from("lovely"));
-    let mut world = String::from("World!");
-    assert_eq!("Hello", mymap[(3, 7)]);
-    assert_eq!("", mymap[(8, 9)]);
-    assert_eq!("lovely", mymap[(3, 4)]);
-    assert_eq!("World!", world);
+    let holey: Map<u32, _> = Map::from_element((Evens { n: 4 }, Upto::new(2)), &1);
+    assert_eq!(&[2, 0, 2, 0], holey.reduce_rows(0, |acc, _, &v| acc + v).as_slice());
+    assert_eq!(&[2, 2], holey.reduce_cols(0, |acc, _, &v| acc + v).as_slice());
 
-    mymap.swap((4, 3), &mut world);
-    assert_eq!("Hello", mymap[(3, 7)]);
-    assert_eq!("", mymap[(8, 9)]);
-    assert_eq!("World!", mymap[(3, 4)]);
-    assert_eq!("lovely", world);
+    // Only the hasher that is returned needs to be `Clone`.
+    let cols: DynHash<usize> = Box::new(Upto::new(3));
+    let dyn_map = Map::from_fn((Upto::new(2), cols), |(i, j)| i * 10 + j);
+    assert_eq!(&[3, 33], dyn_map.reduce_rows(0, |acc, _, &v| acc + v).as_slice());
 }
 
 #[test]
-fn test_map_iter() {
-    let mut mymap = Map::new(Pairs::new(3));
-    mymap.insert((0, 1), 42);
-    mymap.insert((1, 2), 123);
-    mymap.insert((1, 1), 0xCAFE);
-    mymap[(1, 0)] = 5;
-    let value_vec = mymap.values().map(|x| *x).collect::<Vec<_>>();
-    assert_eq!(vec![0, 5, 0xCAFE, 0, 123, 0], value_vec);
-    let entry_vec = mymap.iter().map(|((a, b), &v)| (a, b, v))
-                         .collect::<Vec<_>>();
-    assert_eq!(vec![(0, 0, 0), (0, 1, 5),
-                    (1, 1, 0xCAFE), (0, 2, 0),
-                    (1, 2, 123), (2, 2, 0),], entry_vec);
+fn test_map_row_col() {
+    let mut map = Map::from_fn((Upto::new(2), Upto::new(3)), |(i, j)| i * 10 + j);
+    assert_eq!(vec![(0, &10), (1, &11), (2, &12)], map.row(1).collect::<Vec<_>>());
+    assert_eq!(vec![(0, &2), (1, &12)], map.col(2).collect::<Vec<_>>());
+    for (_, v) in map.row_mut(0) {
+        *v += 100;
+    }
+    for (i, v) in map.col_mut(1) {
+        *v = i;
+    }
+    assert_eq!(&[100, 0, 102, 10, 1, 12], map.as_slice());
+
+    let mut holey: Map<u32, _> = Map::new((Evens { n: 4 }, Upto::new(2)));
+    holey.insert((2, 1), 5);
+    assert_eq!(vec![(0, &0), (2, &5)], holey.col(1).collect::<Vec<_>>());
 }
 
 #[test]
-fn test_map_clone() {
-    let mut mymap = Map::new(Pairs::new(10));
-    mymap.insert((3, 7), String::from("Hello"));
-    mymap[(7, 3)].push(' ');
-    mymap.insert((4, 3), String::from("lovely"));
-    mymap.insert((2, 9), String::from("World!"));
-    let othermap = mymap.clone();
-    assert_eq!("Hello ", othermap.get((3, 7)));
-    assert_eq!("Hello ", othermap.get((7, 3)));
-    assert_eq!("", othermap.get((2, 2)));
-    assert_eq!("World!", othermap[(2, 9)]);
-    assert_eq!("World!", othermap[(9, 2)]);
-    assert_eq!("", othermap.get((7, 4)));
-    assert_eq!("", othermap.get((6, 6)));
+fn test_map_values_double_ended() {
+    let map = Map::from_fn(Upto::new(5), |i| i * 10);
+    let mut values = map.values();
+    assert_eq!(5, values.len());
+    assert_eq!(Some(&40), values.next_back());
+    assert_eq!(Some(&0), values.next());
+    assert_eq!(3, values.len());
+    assert_eq!(vec![&30, &20, &10], values.rev().collect::<Vec<_>>());
+
+    let mut holey: Map<usize, _> = Map::new(Evens { n: 7 });
+    for (k, v) in holey.iter_mut() {
+        *v = k + 1;
+    }
+    assert_eq!(4, holey.values().len());
+    assert_eq!(vec![&7, &5, &3, &1], holey.values().rev().collect::<Vec<_>>());
+    assert_eq!(vec![&3, &5], holey.values_range(1..6).collect::<Vec<_>>());
+    assert_eq!(2, holey.values_range(1..6).len());
+    let mut values = holey.values();
+    assert_eq!(Some(&1), values.next());
+    assert_eq!(Some(&7), values.next_back());
+    assert_eq!(Some(&5), values.next_back());
+    assert_eq!(Some(&3), values.next());
+    assert_eq!(None, values.next());
+    assert_eq!(None, values.next_back());
+
+    let mut values_mut = holey.values_mut();
+    assert_eq!(4, values_mut.len());
+    *values_mut.next_back().unwrap() = 70;
+    assert_eq!(3, values_mut.len());
+    assert_eq!(&[1, 0, 3, 0, 5, 0, 70], holey.as_slice());
+}
+
+#[test]
+fn test_array_map() {
+    let mut pieces: ArrayMap<u8, Pieces, { Pieces::SIZE }> = ArrayMap::new_inline(Pieces);
+    pieces[Piece::new(Color::Black, PieceKind::Queen)] = 1;
+    pieces[Piece::new(Color::White, PieceKind::Pawn)] += 8;
+    assert_eq!(9, pieces.values().map(|&v| v as u32).sum::<u32>());
+    assert_eq!(12, pieces.len());
+}
+
+#[test]
+#[should_panic]
+fn test_array_map_wrong_size() {
+    let _: ArrayMap<u8, _, 4> = ArrayMap::new_inline(Upto::new(5));
+}
+
+::static_map! {
+    /// Hit counters, with some initial hits.
+    static HITS: Map<AtomicU32, Pairs> = Pairs::new(3), {
+        (0, 1) => AtomicU32::new(5),
+        (2, 2) => AtomicU32::new(1),
+    }
+}
+
+::static_map! {
+    /// Visits per square, built at compile time.
+    static VISITS: ArrayMap<AtomicU32, Squares, 64> = Squares,
+        [const { AtomicU32::new(0) }; 64]
+}
+
+#[test]
+fn test_static_map() {
+    use std::sync::atomic::Ordering;
+
+    assert_eq!(5, HITS[(1, 0)].load(Ordering::Relaxed));
+    HITS[(2, 2)].fetch_add(2, Ordering::Relaxed);
+    assert_eq!(3, HITS[(2, 2)].load(Ordering::Relaxed));
+    assert_eq!(0, HITS[(0, 2)].load(Ordering::Relaxed));
+
+    let e4 = Square::new(4, 3);
+    VISITS[e4].fetch_add(1, Ordering::Relaxed);
+    assert_eq!(1, VISITS[e4].load(Ordering::Relaxed));
+    assert_eq!(1, VISITS.values().map(|v| v.load(Ordering::Relaxed)).sum::<u32>());
+}
+
+#[test]
+fn test_array_map_from_array() {
+    const LIGHTS: ArrayMap<u8, EnumHash<Light>, 3> =
+        ArrayMap::from_array(EnumHash::new(), [40, 5, 30]);
+    assert_eq!(30, LIGHTS[Light::Green]);
+}
+
+#[test]
+#[should_panic]
+fn test_array_map_from_array_wrong_length() {
+    ArrayMap::<u8, _, 3>::from_array(Squares, [0; 3]);
+}
+
+#[test]
+#[should_panic]
+fn test_map_view_wrong_length() {
+    MapView::from_storage(Upto::new(3), &[1, 2][..]);
 }
 
 /* === Actual tests: Map, clone-only value type === */
@@ -172,6 +2045,35 @@ fn test_map_clone_init() {
     assert_eq!(1337, mymap.get((5, 5)).0);
 }
 
+#[test]
+fn test_map_into_iter() {
+    let mymap = Map::from_initial(Evens { n: 5 },
+        vec![Foo(1), Foo(2), Foo(3), Foo(4), Foo(5)]);
+    let mut entries = Vec::new();
+    for (k, v) in mymap {
+        entries.push((k, v.0));
+    }
+    assert_eq!(vec![(0, 1), (2, 3), (4, 5)], entries);
+
+    let mymap = Map::from_initial(Upto::new(3), vec![Foo(7), Foo(8), Foo(9)]);
+    assert_eq!(vec![7, 8, 9], mymap.into_values().map(|v| v.0).collect::<Vec<_>>());
+}
+
+#[test]
+fn test_map_from_fn() {
+    let coords = Map::from_fn(Squares, |sq| sq.file() * 10 + sq.rank());
+    assert_eq!(74, coords[Square::new(7, 4)]);
+
+    let mymap = Map::from_fn(Pairs::new(4), |(a, b)| Foo((a * b) as u32));
+    assert_eq!(6, mymap.get((3, 2)).0);
+}
+
+#[test]
+#[should_panic]
+fn test_map_from_fn_holes() {
+    Map::from_fn(Evens { n: 4 }, |k| k);
+}
+
 /* === Actual tests: Set === */
 
 #[test]
@@ -302,6 +2204,307 @@ fn test_set_iter() {
     assert_eq!(vec![(0, 1), (1, 4), (3, 4), (6, 7)], as_vec);
 }
 
+#[test]
+fn test_set_range() {
+    let mut myset = Set::new(CharRange::new('a'..='z'));
+    for c in "the quick brown fox".chars().filter(|c| c.is_alphabetic()) {
+        myset.insert(c);
+    }
+    assert_eq!("hik", myset.range('g'..'n').collect::<String>());
+    assert_eq!("hikn", myset.range('g'..='n').collect::<String>());
+    assert_eq!("wx", myset.range('v'..).collect::<String>());
+    assert_eq!("bce", myset.range(..'f').collect::<String>());
+    let mut iter = myset.range('u'..'y');
+    assert_eq!(Some('u'), iter.next());
+    assert_eq!(Some('w'), iter.next());
+    assert_eq!(Some('x'), iter.next());
+    assert_eq!(None, iter.next());
+}
+
+#[test]
+fn test_set_holes() {
+    let mut myset = Set::new(Evens { n: 5 });
+    myset.insert(0);
+    myset.insert(4);
+    assert_eq!(false, myset.is_full());
+    assert_eq!(vec![0, 4], myset.iter().collect::<Vec<_>>());
+    myset.insert(2);
+    assert_eq!(true, myset.is_full());
+}
+
+#[test]
+fn test_set_contains_ref() {
+    let mut myset = Set::new(Combinations::new(5, 2));
+    myset.insert(vec![4, 1]);
+    assert_eq!(true, myset.contains_ref(&vec![1, 4]));
+    assert_eq!(false, myset.contains_ref(&vec![1, 3]));
+}
+
+#[test]
+fn test_set_contains_by() {
+    let mut myset = Set::new(BoundedMultisets::new(vec![1, 2]));
+    myset.insert(vec![1, 2]);
+    assert_eq!(true, myset.contains_by(&[1, 2][..]));
+    assert_eq!(false, myset.contains_by(&[0, 2][..]));
+}
+
+#[test]
+fn test_set_parts() {
+    let mut myset = Set::new(Pairs::new(4));
+    myset.insert((3, 1));
+    let (hash, blocks) = myset.into_parts();
+    assert_eq!(1, blocks.len());
+    assert_eq!(1, blocks[0].count_ones());
+    let myset = Set::from_parts(hash, blocks).unwrap();
+    assert_eq!(true, myset.contains((1, 3)));
+
+    let (_, blocks) = Set::new(Upto::new(32)).into_parts();
+    let err = WrongLength { expected: 2, actual: 1 };
+    assert_eq!(Some(InvalidBlocks::WrongLength(err)),
+               Set::from_parts(Upto::new(33), blocks).err());
+
+    let blocks = [0b101];
+    let view = Set::from_parts(Upto::new(3), &blocks[..]).unwrap();
+    assert_eq!(vec![0, 2], view.iter().collect::<Vec<_>>());
+    assert_eq!(Some(InvalidBlocks::StrayBit(3)),
+               Set::from_parts(Upto::new(3), &[0b1001][..]).err());
+
+    let mut full = Set::new(Evens { n: 5 });
+    full.fill();
+    let (hash, blocks) = full.into_parts();
+    assert_eq!(0b10101, blocks[0]);
+    let full = Set::from_parts(hash, blocks).unwrap();
+    assert_eq!(3, full.len());
+    assert_eq!(true, full.is_full());
+    assert_eq!(true, (!full).is_empty());
+    assert_eq!(Some(InvalidBlocks::StrayBit(1)),
+               Set::from_parts(Evens { n: 5 }, &[0b111][..]).err());
+}
+
+#[test]
+fn test_set_memory_usage() {
+    assert_eq!(0, Set::new(Upto::new(0)).memory_usage());
+    assert_eq!(4, Set::new(Upto::new(10)).memory_usage());
+    assert_eq!(8, Set::new(Upto::new(33)).memory_usage());
+}
+
+#[test]
+fn test_set_len() {
+    let mut set = Set::new(Upto::new(100));
+    assert_eq!(0, set.len());
+    assert_eq!(0.0, set.density());
+    for i in (0..100).step_by(4) {
+        set.insert(i);
+    }
+    set.insert(99);
+    assert_eq!(26, set.len());
+    assert_eq!(0.26, set.density());
+    set.erase(0);
+    assert_eq!(25, set.len());
+
+    let empty = Set::new(Upto::new(0));
+    assert_eq!(0, empty.len());
+    assert_eq!(0.0, empty.density());
+
+    let mut holey = Set::new(Evens { n: 8 });
+    holey.insert_all(vec![0, 2]);
+    assert_eq!(0.5, holey.density());
+    holey.insert_all(vec![4, 6]);
+    assert_eq!(1.0, holey.density());
+}
+
+#[test]
+fn test_set_clear() {
+    let mut set = Set::new(Evens { n: 6 });
+    assert!(set.is_empty());
+    assert!(!set.insert(0));
+    assert!(set.insert(0));
+    assert_eq!(1, set.len());
+    assert!(!set.erase(2));
+    assert_eq!(1, set.len());
+    set.insert(2);
+    set.insert(4);
+    assert_eq!(3, set.len());
+    assert!(set.is_full());
+    set.clear();
+    assert!(set.is_empty());
+    assert!(!set.contains(4));
+    assert_eq!(0, set.iter().count());
+}
+
+#[test]
+fn test_set_bulk() {
+    let mut set = Set::new(Upto::new(70));
+    set.fill();
+    assert!(set.is_full());
+    assert_eq!(70, set.len());
+    set.erase_all(vec![3, 5, 3, 69]);
+    assert_eq!(67, set.len());
+    assert!(!set.contains(69));
+    set.clear();
+    set.insert_all(10..20);
+    assert_eq!((10..20).collect::<Vec<_>>(), set.iter().collect::<Vec<_>>());
+
+    let mut holey = Set::new(Evens { n: 7 });
+    holey.fill();
+    assert_eq!(4, holey.len());
+    assert_eq!(vec![0, 2, 4, 6], holey.iter().collect::<Vec<_>>());
+}
+
+#[test]
+fn test_set_toggle() {
+    let mut set = Set::new(Upto::new(10));
+    assert!(!set.toggle(3));
+    assert!(set.contains(3));
+    assert!(set.toggle(3));
+    assert!(!set.contains(3));
+    for i in 0..10 {
+        set.set(i, i % 3 == 0);
+    }
+    assert_eq!(vec![0, 3, 6, 9], set.iter().collect::<Vec<_>>());
+    assert!(set.set(6, false));
+    assert!(!set.set(6, false));
+    assert_eq!(3, set.len());
+}
+
+#[test]
+fn test_set_retain() {
+    let mut set = Set::new(Upto::new(20));
+    set.insert_all(5..15);
+    let mut seen = Vec::new();
+    set.retain(|k| {
+        seen.push(k);
+        k % 2 == 1
+    });
+    assert_eq!((5..15).collect::<Vec<_>>(), seen);
+    assert_eq!(vec![5, 7, 9, 11, 13], set.iter().collect::<Vec<_>>());
+    assert_eq!(5, set.len());
+}
+
+#[test]
+fn test_set_drain() {
+    let mut set = Set::new(Upto::new(20));
+    set.insert_all(vec![3, 17, 8]);
+    let mut drain = set.drain();
+    assert_eq!(3, drain.len());
+    assert_eq!(Some(3), drain.next());
+    assert_eq!(vec![8, 17], drain.collect::<Vec<_>>());
+    assert!(set.is_empty());
+
+    set.insert_all(vec![1, 2, 3]);
+    assert_eq!(Some(1), set.drain().next());
+    assert!(set.is_empty());
+    assert_eq!(0, set.iter().count());
+}
+
+#[test]
+fn test_set_pop() {
+    let mut set = Set::new(Upto::new(100));
+    set.insert_all(vec![70, 5, 33, 32]);
+    let mut popped = Vec::new();
+    while let Some(k) = set.pop() {
+        popped.push(k);
+        if k == 32 {
+            set.insert(99);
+        }
+    }
+    assert_eq!(vec![5, 32, 33, 70, 99], popped);
+    assert!(set.is_empty());
+    assert_eq!(None, Set::new(Upto::new(0)).pop());
+}
+
+#[test]
+fn test_set_algebra() {
+    let mut a = Set::new(Upto::new(40));
+    a.insert_all(vec![1, 2, 3, 35]);
+    let mut b = Set::new(Upto::new(40));
+    b.insert_all(vec![3, 4, 35, 39]);
+    let keys = |s: Set<Upto>| s.iter().collect::<Vec<_>>();
+
+    let union = a.union(&b);
+    assert_eq!(6, union.len());
+    assert_eq!(vec![1, 2, 3, 4, 35, 39], keys(union));
+    let intersection = a.intersection(&b);
+    assert_eq!(2, intersection.len());
+    assert_eq!(vec![3, 35], keys(intersection));
+    assert_eq!(vec![1, 2], keys(a.difference(&b)));
+    assert_eq!(vec![4, 39], keys(b.difference(&a)));
+    assert_eq!(vec![1, 2, 4, 39], keys(a.symmetric_difference(&b)));
+    assert_eq!(4, a.len());
+}
+
+#[test]
+fn test_set_algebra_in_place() {
+    let mut visited = Set::new(Upto::new(50));
+    visited.insert_all(0..10);
+    let mut next = Set::new(Upto::new(50));
+    next.insert_all(vec![8, 9, 10, 45]);
+    next.difference_with(&visited);
+    assert_eq!(vec![10, 45], next.iter().collect::<Vec<_>>());
+    visited.union_with(&next);
+    assert_eq!(12, visited.len());
+    assert!(visited.contains(45));
+
+    let mut a = next.clone();
+    a.symmetric_difference_with(&visited);
+    assert_eq!((0..10).collect::<Vec<_>>(), a.iter().collect::<Vec<_>>());
+    a.intersect_with(&next);
+    assert!(a.is_empty());
+}
+
+#[test]
+fn test_set_ops() {
+    let mut reachable = Set::new(Evens { n: 10 });
+    reachable.insert_all(vec![0, 2, 4]);
+    let mut visited = Set::new(Evens { n: 10 });
+    visited.insert_all(vec![2, 8]);
+    let keys = |s: &Set<Evens>| s.iter().collect::<Vec<_>>();
+
+    let frontier = &reachable & !&visited;
+    assert_eq!(vec![0, 4], keys(&frontier));
+    assert_eq!(vec![0, 4, 6], keys(&!&visited));
+    assert_eq!(3, (!visited.clone()).len());
+    assert_eq!(vec![0, 2, 4, 8], keys(&(&reachable | &visited)));
+    assert_eq!(vec![0, 4, 8], keys(&(reachable.clone() ^ &visited)));
+    assert_eq!(vec![2], keys(&(reachable.clone() & visited.clone())));
+
+    let mut all = frontier.clone();
+    all |= &visited;
+    assert_eq!(vec![0, 2, 4, 8], keys(&all));
+    all ^= &reachable;
+    assert_eq!(vec![8], keys(&all));
+    all &= &frontier;
+    assert!(all.is_empty());
+    assert!((!all).is_full());
+}
+
+#[test]
+fn test_set_relations() {
+    let mut small = Set::new(Upto::new(70));
+    small.insert_all(vec![1, 65]);
+    let mut big = Set::new(Upto::new(70));
+    big.insert_all(vec![1, 2, 65]);
+    let mut other = Set::new(Upto::new(70));
+    other.insert_all(vec![2, 66]);
+    let empty = Set::new(Upto::new(70));
+
+    assert!(small.is_subset(&big));
+    assert!(!big.is_subset(&small));
+    assert!(big.is_superset(&small));
+    assert!(small.is_subset(&small));
+    assert!(empty.is_subset(&small));
+    assert!(!other.is_subset(&big));
+    assert!(small.is_disjoint(&other));
+    assert!(!big.is_disjoint(&other));
+    assert!(empty.is_disjoint(&empty));
+}
+
+#[test]
+#[should_panic]
+fn test_set_relations_size_mismatch() {
+    Set::new(Upto::new(3)).is_disjoint(&Set::new(Upto::new(4)));
+}
+
 #[test]
 fn test_set_clone() {
     let mut myset = Set::new(Pairs::new(10));